@@ -0,0 +1,53 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use matchers::Pattern;
+use std::io::{self, Read, Write};
+
+/// An `io::Read` that does not implement any buffering of its own, so that
+/// every call to `read` has a fixed cost, as with files and sockets.
+struct Unbuffered<'a>(&'a [u8]);
+
+impl Read for Unbuffered<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = std::hint::black_box(self.0).read(buf)?;
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+/// The previous implementation of `read_matches`, which read a single byte
+/// at a time using `io::Bytes`.
+#[allow(clippy::unbuffered_bytes)]
+fn read_matches_bytewise(pattern: &Pattern, io: impl Read) -> io::Result<bool> {
+    let mut matcher = pattern.matcher();
+    for byte in io.bytes() {
+        matcher.write_all(&[byte?])?;
+    }
+    Ok(matcher.is_matched())
+}
+
+fn bench_read(c: &mut Criterion) {
+    let pattern = Pattern::new("needle: [a-z]+").unwrap();
+    let mut group = c.benchmark_group("read_matches");
+    for &len in &[1024, 64 * 1024, 1024 * 1024] {
+        let mut input = "haystack ".repeat(len / 9).into_bytes();
+        input.extend_from_slice(b"needle: found");
+
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("bytewise", len), &input, |b, input| {
+            b.iter(|| read_matches_bytewise(&pattern, Unbuffered(input)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("chunked", len), &input, |b, input| {
+            b.iter(|| pattern.read_matches(Unbuffered(input)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("buffered", len), &input, |b, input| {
+            b.iter(|| {
+                let reader = io::BufReader::new(Unbuffered(input));
+                pattern.read_matches_buf(reader).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_read);
+criterion_main!(benches);
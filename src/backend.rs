@@ -0,0 +1,334 @@
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec;
+use core::fmt;
+
+use regex_automata::dfa::{dense, sparse, Automaton};
+use regex_automata::util::primitives::StateID;
+use regex_automata::util::start;
+use regex_automata::Anchored;
+
+#[cfg(feature = "hybrid")]
+use core::cell::RefCell;
+#[cfg(feature = "hybrid")]
+use regex_automata::hybrid::{self, LazyStateID};
+
+/// An automaton that can be used to match a [`Pattern`].
+///
+/// This trait is implemented for the regex engines provided by
+/// `regex-automata` which can be driven one byte at a time:
+///
+/// - [`dense::DFA`], the default, which is fully compiled ahead of time,
+/// - [`sparse::DFA`], which is also compiled ahead of time, but uses a more
+///   compact representation which is slower to match,
+/// - [`hybrid::dfa::DFA`] (with the `hybrid` feature), a lazy DFA which
+///   builds states on demand as input is matched.
+///
+/// It is also implemented for references to, and `Arc`s of, any of these
+/// types. This trait is sealed, and may not be implemented outside of this
+/// crate.
+///
+/// [`Pattern`]: ../struct.Pattern.html
+/// [`dense::DFA`]: https://docs.rs/regex-automata/0.4/regex_automata/dfa/dense/struct.DFA.html
+/// [`sparse::DFA`]: https://docs.rs/regex-automata/0.4/regex_automata/dfa/sparse/struct.DFA.html
+/// [`hybrid::dfa::DFA`]: https://docs.rs/regex-automata/0.4/regex_automata/hybrid/dfa/struct.DFA.html
+pub trait Backend: sealed::Automaton {}
+
+impl<A: sealed::Automaton + ?Sized> Backend for A {}
+
+pub(crate) mod sealed {
+    use super::*;
+
+    /// The operations a `Matcher` needs from its automaton.
+    ///
+    /// This is a separate, unnameable trait so that users can't drive the
+    /// automaton with state IDs that it didn't produce.
+    pub trait Automaton {
+        /// The identifier of a state in this automaton.
+        type State: Copy + Ord + fmt::Debug;
+
+        /// Mutable scratch space required by each `Matcher`.
+        type Cache: Clone + fmt::Debug;
+
+        /// Returns a new cache for matching a single input.
+        fn create_cache(&self) -> Self::Cache;
+
+        /// Returns the state in which matching an input begins.
+        fn start_state(&self, cache: &mut Self::Cache, anchored: Anchored) -> Self::State;
+
+        /// Returns the start state to use when a matcher is reset, given the
+        /// start state that the matcher previously cached.
+        fn restart_state(
+            &self,
+            cache: &mut Self::Cache,
+            start: Self::State,
+            anchored: Anchored,
+        ) -> Self::State;
+
+        /// Returns the state that follows `state` on the given byte of input.
+        fn next_state(&self, cache: &mut Self::Cache, state: Self::State, input: u8)
+            -> Self::State;
+
+        /// Returns the state that follows `state` at the end of the input.
+        fn next_eoi_state(&self, cache: &Self::Cache, state: Self::State) -> Self::State;
+
+        /// Returns `true` if `state` is a match state.
+        fn is_match_state(&self, state: Self::State) -> bool;
+
+        /// Returns `true` if `state` is a dead state, from which no input can
+        /// lead to a match.
+        fn is_dead_state(&self, state: Self::State) -> bool;
+
+        /// Returns `true` if every input following `state`, including the
+        /// empty input, leads to a match.
+        ///
+        /// This may conservatively return `false` if it can't be determined.
+        fn is_universal_state(&self, cache: &Self::Cache, state: Self::State) -> bool;
+    }
+}
+
+// === impl &'a Automaton, Arc<Automaton> ===
+
+macro_rules! deref_impl {
+    ($($ty:ty),+) => {
+        $(
+            impl<A: sealed::Automaton + ?Sized> sealed::Automaton for $ty {
+                type State = A::State;
+                type Cache = A::Cache;
+
+                #[inline]
+                fn create_cache(&self) -> Self::Cache {
+                    (**self).create_cache()
+                }
+
+                #[inline]
+                fn start_state(&self, cache: &mut Self::Cache, anchored: Anchored) -> Self::State {
+                    (**self).start_state(cache, anchored)
+                }
+
+                #[inline]
+                fn restart_state(
+                    &self,
+                    cache: &mut Self::Cache,
+                    start: Self::State,
+                    anchored: Anchored,
+                ) -> Self::State {
+                    (**self).restart_state(cache, start, anchored)
+                }
+
+                #[inline]
+                fn next_state(
+                    &self,
+                    cache: &mut Self::Cache,
+                    state: Self::State,
+                    input: u8,
+                ) -> Self::State {
+                    (**self).next_state(cache, state, input)
+                }
+
+                #[inline]
+                fn next_eoi_state(&self, cache: &Self::Cache, state: Self::State) -> Self::State {
+                    (**self).next_eoi_state(cache, state)
+                }
+
+                #[inline]
+                fn is_match_state(&self, state: Self::State) -> bool {
+                    (**self).is_match_state(state)
+                }
+
+                #[inline]
+                fn is_dead_state(&self, state: Self::State) -> bool {
+                    (**self).is_dead_state(state)
+                }
+
+                #[inline]
+                fn is_universal_state(&self, cache: &Self::Cache, state: Self::State) -> bool {
+                    (**self).is_universal_state(cache, state)
+                }
+            }
+        )+
+    };
+}
+
+deref_impl!(&'_ A, Arc<A>);
+
+// === impl dense::DFA ===
+
+impl<T: AsRef<[u32]>> sealed::Automaton for dense::DFA<T> {
+    type State = StateID;
+    type Cache = ();
+
+    #[inline]
+    fn create_cache(&self) -> Self::Cache {}
+
+    #[inline]
+    fn start_state(&self, _: &mut Self::Cache, anchored: Anchored) -> Self::State {
+        let config = start::Config::new().anchored(anchored);
+        Automaton::start_state(self, &config).unwrap()
+    }
+
+    #[inline]
+    fn restart_state(&self, _: &mut Self::Cache, start: Self::State, _: Anchored) -> Self::State {
+        start
+    }
+
+    #[inline]
+    fn next_state(&self, _: &mut Self::Cache, state: Self::State, input: u8) -> Self::State {
+        // It's safe to call `next_state_unchecked` since this trait can't be
+        // used outside of this crate, and the matcher only ever passes in
+        // states that were produced by the same valid DFA.
+        unsafe { self.next_state_unchecked(state, input) }
+    }
+
+    #[inline]
+    fn next_eoi_state(&self, _: &Self::Cache, state: Self::State) -> Self::State {
+        Automaton::next_eoi_state(self, state)
+    }
+
+    #[inline]
+    fn is_match_state(&self, state: Self::State) -> bool {
+        Automaton::is_match_state(self, state)
+    }
+
+    #[inline]
+    fn is_dead_state(&self, state: Self::State) -> bool {
+        Automaton::is_dead_state(self, state)
+    }
+
+    fn is_universal_state(&self, _: &Self::Cache, state: Self::State) -> bool {
+        is_universal_state(self, state)
+    }
+}
+
+// === impl sparse::DFA ===
+
+impl<T: AsRef<[u8]>> sealed::Automaton for sparse::DFA<T> {
+    type State = StateID;
+    type Cache = ();
+
+    #[inline]
+    fn create_cache(&self) -> Self::Cache {}
+
+    #[inline]
+    fn start_state(&self, _: &mut Self::Cache, anchored: Anchored) -> Self::State {
+        let config = start::Config::new().anchored(anchored);
+        Automaton::start_state(self, &config).unwrap()
+    }
+
+    #[inline]
+    fn restart_state(&self, _: &mut Self::Cache, start: Self::State, _: Anchored) -> Self::State {
+        start
+    }
+
+    #[inline]
+    fn next_state(&self, _: &mut Self::Cache, state: Self::State, input: u8) -> Self::State {
+        // It's safe to call `next_state_unchecked` since this trait can't be
+        // used outside of this crate, and the matcher only ever passes in
+        // states that were produced by the same valid DFA.
+        unsafe { self.next_state_unchecked(state, input) }
+    }
+
+    #[inline]
+    fn next_eoi_state(&self, _: &Self::Cache, state: Self::State) -> Self::State {
+        Automaton::next_eoi_state(self, state)
+    }
+
+    #[inline]
+    fn is_match_state(&self, state: Self::State) -> bool {
+        Automaton::is_match_state(self, state)
+    }
+
+    #[inline]
+    fn is_dead_state(&self, state: Self::State) -> bool {
+        Automaton::is_dead_state(self, state)
+    }
+
+    fn is_universal_state(&self, _: &Self::Cache, state: Self::State) -> bool {
+        is_universal_state(self, state)
+    }
+}
+
+// === impl hybrid::dfa::DFA ===
+
+// A lazy DFA computes transitions on demand, so it needs mutable access to its
+// cache even when only peeking at the end-of-input transition. The cache is
+// wrapped in a `RefCell` so that this can be done through a shared reference.
+//
+// The lazy DFAs built by this crate never set a minimum cache clear count and
+// never have quit bytes, so computing a transition can't fail.
+#[cfg(feature = "hybrid")]
+impl sealed::Automaton for hybrid::dfa::DFA {
+    type State = LazyStateID;
+    type Cache = RefCell<hybrid::dfa::Cache>;
+
+    #[inline]
+    fn create_cache(&self) -> Self::Cache {
+        RefCell::new(hybrid::dfa::DFA::create_cache(self))
+    }
+
+    #[inline]
+    fn start_state(&self, cache: &mut Self::Cache, anchored: Anchored) -> Self::State {
+        let config = start::Config::new().anchored(anchored);
+        hybrid::dfa::DFA::start_state(self, cache.get_mut(), &config).unwrap()
+    }
+
+    #[inline]
+    fn restart_state(
+        &self,
+        cache: &mut Self::Cache,
+        _: Self::State,
+        anchored: Anchored,
+    ) -> Self::State {
+        // The cached start state is invalidated if the cache has been cleared
+        // since it was computed, so look it up again. Start states are
+        // themselves cached, so this is cheap.
+        sealed::Automaton::start_state(self, cache, anchored)
+    }
+
+    #[inline]
+    fn next_state(&self, cache: &mut Self::Cache, state: Self::State, input: u8) -> Self::State {
+        hybrid::dfa::DFA::next_state(self, cache.get_mut(), state, input).unwrap()
+    }
+
+    #[inline]
+    fn next_eoi_state(&self, cache: &Self::Cache, state: Self::State) -> Self::State {
+        hybrid::dfa::DFA::next_eoi_state(self, &mut cache.borrow_mut(), state).unwrap()
+    }
+
+    #[inline]
+    fn is_match_state(&self, state: Self::State) -> bool {
+        state.is_match()
+    }
+
+    #[inline]
+    fn is_dead_state(&self, state: Self::State) -> bool {
+        state.is_dead()
+    }
+
+    fn is_universal_state(&self, _: &Self::Cache, _: Self::State) -> bool {
+        // Exploring the states reachable from `state` would add them to the
+        // cache, which may clear it and invalidate the matcher's current
+        // state, so don't try.
+        false
+    }
+}
+
+/// Returns `true` if every state reachable from `state` (including `state`
+/// itself) is a match state at the end of the input.
+fn is_universal_state<A: Automaton>(dfa: &A, state: StateID) -> bool {
+    let mut seen = BTreeSet::new();
+    let mut stack = vec![state];
+    seen.insert(state);
+    while let Some(id) = stack.pop() {
+        if !dfa.is_match_state(dfa.next_eoi_state(id)) {
+            return false;
+        }
+        for byte in 0..=u8::MAX {
+            let next = dfa.next_state(id, byte);
+            if seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    true
+}
@@ -0,0 +1,104 @@
+//! Helpers for precompiling patterns in build scripts.
+//!
+//! Compiling a large pattern into a DFA can take a noticeable amount of
+//! time. Rather than paying that cost every time a program starts, patterns
+//! may be compiled ahead of time in a `build.rs` script using
+//! [`compile_to_file`], and embedded in the final binary with the
+//! [`include_pattern!`] macro. Since the pattern is compiled when the crate
+//! is built, an invalid regex fails the build rather than returning an error
+//! at runtime.
+//!
+//! For example, in `build.rs`:
+//! ```no_run
+//! use matchers::Pattern;
+//! use std::{env, path::Path};
+//!
+//! let pattern = Pattern::new(r"error: .+").expect("regex is not invalid");
+//! let out_dir = env::var("OUT_DIR").unwrap();
+//! matchers::build::compile_to_file(&pattern, Path::new(&out_dir).join("error.dfa"))
+//!     .expect("failed to write precompiled pattern");
+//! ```
+//!
+//! And then, in the crate itself:
+//! ```ignore
+//! let pattern = matchers::include_pattern!(concat!(env!("OUT_DIR"), "/error.dfa"));
+//! assert!(pattern.display_matches(&"error: something went wrong"));
+//! ```
+//!
+//! [`compile_to_file`]: fn.compile_to_file.html
+//! [`include_pattern!`]: ../macro.include_pattern.html
+#[cfg(feature = "std")]
+use crate::Pattern;
+#[cfg(feature = "std")]
+use std::{env, fs, io, path::Path};
+
+#[cfg(feature = "std")]
+use regex_automata::dfa::dense::DFA;
+
+/// Serializes `pattern` and writes it to the file at `path`, so that it can
+/// be embedded in a binary using [`include_pattern!`].
+///
+/// When called from a build script, the pattern is serialized using the
+/// endianness of the target being built for, as indicated by Cargo's
+/// `CARGO_CFG_TARGET_ENDIAN` environment variable. Otherwise, the native
+/// endianness of the current target is used.
+///
+/// [`include_pattern!`]: ../macro.include_pattern.html
+#[cfg(feature = "std")]
+pub fn compile_to_file<T: AsRef<[u32]>>(
+    pattern: &Pattern<DFA<T>>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let (bytes, padding) = match env::var("CARGO_CFG_TARGET_ENDIAN").as_deref() {
+        Ok("little") => pattern.to_bytes_little_endian(),
+        Ok("big") => pattern.to_bytes_big_endian(),
+        _ => pattern.to_bytes_native_endian(),
+    };
+    fs::write(path, &bytes[padding..])
+}
+
+/// Embeds a pattern precompiled by [`build::compile_to_file`] in the binary,
+/// and returns it as a `Pattern<DFA<&'static [u32]>>`.
+///
+/// The path is resolved in the same way as for [`include_bytes!`]. The
+/// pattern's bytes are stored in the binary with the alignment required to
+/// use them in place, so loading the pattern only validates the DFA, and
+/// never allocates. The returned pattern may be stored in a `static` using a
+/// lazy initialization primitive such as `std::sync::OnceLock`.
+///
+/// # Panics
+///
+/// If the embedded bytes are not a valid serialized pattern for the current
+/// target.
+///
+/// [`build::compile_to_file`]: build/fn.compile_to_file.html
+/// [`include_bytes!`]: https://doc.rust-lang.org/std/macro.include_bytes.html
+#[macro_export]
+macro_rules! include_pattern {
+    ($path:expr) => {{
+        static ALIGNED: &$crate::__private::AlignAs<[u8], u32> = &$crate::__private::AlignAs {
+            _align: [],
+            bytes: *include_bytes!($path),
+        };
+        $crate::Pattern::from_bytes(&ALIGNED.bytes)
+            .expect("precompiled pattern should be valid")
+            .0
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compile_to_file_writes_unpadded_bytes() {
+        let pattern = Pattern::new_anchored("a+b").unwrap();
+        let path = env::temp_dir().join(format!("matchers-{}.dfa", std::process::id()));
+        compile_to_file(&pattern, &path).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (bytes, padding) = pattern.to_bytes_native_endian();
+        assert_eq!(written, &bytes[padding..]);
+    }
+}
@@ -0,0 +1,614 @@
+use crate::captures::{CaptureSource, DEFAULT_CAPTURE_LIMIT};
+use crate::{Error, Pattern, PatternSet};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use regex_automata::dfa::dense;
+use regex_automata::dfa::sparse;
+use regex_automata::nfa::thompson;
+use regex_automata::util::syntax;
+use regex_automata::{Anchored, MatchKind};
+
+#[cfg(feature = "hybrid")]
+use regex_automata::hybrid;
+
+/// A builder for configuring how a [`Pattern`] is compiled.
+///
+/// [`Pattern::new`] and [`Pattern::new_anchored`] compile a regex using the
+/// default settings, so any options have to be spelled out as inline flags
+/// such as `(?i)`. A `PatternBuilder` allows those options to be set
+/// separately from the pattern itself.
+///
+/// For example:
+/// ```
+/// use matchers::PatternBuilder;
+///
+/// let pattern = PatternBuilder::new()
+///     .case_insensitive(true)
+///     .unicode(false)
+///     .anchored(true)
+///     .build("hello [a-z]+")
+///     .expect("regex is not invalid");
+///
+/// assert!(pattern.display_matches(&"HELLO World"));
+/// assert!(!pattern.display_matches(&"well, hello world"));
+/// ```
+///
+/// [`Pattern`]: ../struct.Pattern.html
+/// [`Pattern::new`]: ../struct.Pattern.html#method.new
+/// [`Pattern::new_anchored`]: ../struct.Pattern.html#method.new_anchored
+#[derive(Debug, Clone)]
+pub struct PatternBuilder {
+    syntax: syntax::Config,
+    thompson: thompson::Config,
+    dfa: dense::Config,
+    anchored: Anchored,
+    capture_limit: usize,
+}
+
+// === impl PatternBuilder ===
+
+impl PatternBuilder {
+    /// Returns a new `PatternBuilder` with the default configuration.
+    ///
+    /// By default, patterns are unanchored and use the same defaults as the
+    /// [`regex-automata`] syntax configuration.
+    ///
+    /// [`regex-automata`]: https://docs.rs/regex-automata/0.4.3/regex_automata/util/syntax/struct.Config.html
+    pub fn new() -> Self {
+        Self {
+            syntax: syntax::Config::new(),
+            thompson: thompson::Config::new(),
+            dfa: dense::Config::new(),
+            anchored: Anchored::No,
+            capture_limit: DEFAULT_CAPTURE_LIMIT,
+        }
+    }
+
+    /// Compiles the given regex into a [`Pattern`] using this builder's
+    /// configuration, or returns an error if the regex was invalid or too
+    /// big.
+    ///
+    /// [`Pattern`]: ../struct.Pattern.html
+    pub fn build(&self, pattern: &str) -> Result<Pattern, Error> {
+        let nfa = self.nfa(&[pattern])?;
+        let automaton = dense::Builder::new()
+            .configure(self.dfa.clone())
+            .build_from_nfa(&nfa)
+            .map_err(Error::dfa)?;
+        Ok(Pattern {
+            automaton,
+            anchored: self.anchored,
+            captures: Some(self.capture_source(pattern)),
+        })
+    }
+
+    /// Compiles the given regex into a [`Pattern`] backed by a sparse DFA, or
+    /// returns an error if the regex was invalid or too big.
+    ///
+    /// See [`Pattern::new_sparse`] for details on sparse DFAs.
+    ///
+    /// [`Pattern`]: ../struct.Pattern.html
+    /// [`Pattern::new_sparse`]: ../struct.Pattern.html#method.new_sparse
+    pub fn build_sparse(&self, pattern: &str) -> Result<Pattern<sparse::DFA<Vec<u8>>>, Error> {
+        let dense = self.build(pattern)?;
+        Ok(Pattern {
+            automaton: dense.automaton.to_sparse().map_err(Error::dfa)?,
+            anchored: dense.anchored,
+            captures: dense.captures,
+        })
+    }
+
+    /// Compiles the given regex into a [`Pattern`] backed by a lazy DFA, or
+    /// returns an error if the regex was invalid or too big.
+    ///
+    /// Rather than compiling the entire DFA up front, a lazy DFA builds its
+    /// states on demand as input is matched, caching them in each
+    /// [`Matcher`]. This makes building the pattern much cheaper for regexes
+    /// whose DFAs would be very large, such as those using large Unicode
+    /// classes like `\w{50}`, at the cost of somewhat slower matching.
+    ///
+    /// Since the DFA is built lazily, only the [NFA size
+    /// limit](#method.nfa_size_limit) applies to lazy DFAs.
+    ///
+    /// This method requires the `hybrid` feature flag.
+    ///
+    /// [`Pattern`]: ../struct.Pattern.html
+    /// [`Matcher`]: ../struct.Matcher.html
+    #[cfg(feature = "hybrid")]
+    pub fn build_lazy(&self, pattern: &str) -> Result<Pattern<hybrid::dfa::DFA>, Error> {
+        let nfa = self.nfa(&[pattern])?;
+        let automaton = hybrid::dfa::Builder::new()
+            .build_from_nfa(nfa)
+            .map_err(Error::lazy)?;
+        Ok(Pattern {
+            automaton,
+            anchored: self.anchored,
+            captures: Some(self.capture_source(pattern)),
+        })
+    }
+
+    /// Compiles a [`Pattern`] that matches any input containing at least one
+    /// of the given literal strings, or returns an error if the pattern was
+    /// too big.
+    ///
+    /// See [`Pattern::from_literals`] for details. Only the [`anchored`],
+    /// [`case_insensitive`] and [`capture_limit`] options and the size limits
+    /// apply to literals, and case-insensitivity only applies to ASCII
+    /// letters, since the literals may be arbitrary bytes.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::PatternBuilder;
+    ///
+    /// let pattern = PatternBuilder::new()
+    ///     .case_insensitive(true)
+    ///     .build_literals(["curl/", "python-requests/"])
+    ///     .expect("literals are not too big");
+    ///
+    /// assert!(pattern.display_matches(&"Python-Requests/2.31.0"));
+    /// assert!(!pattern.display_matches(&"Mozilla/5.0"));
+    /// ```
+    ///
+    /// [`Pattern`]: ../struct.Pattern.html
+    /// [`Pattern::from_literals`]: ../struct.Pattern.html#method.from_literals
+    /// [`anchored`]: #method.anchored
+    /// [`case_insensitive`]: #method.case_insensitive
+    /// [`capture_limit`]: #method.capture_limit
+    pub fn build_literals<I, L>(&self, literals: I) -> Result<Pattern, Error>
+    where
+        I: IntoIterator<Item = L>,
+        L: AsRef<[u8]>,
+    {
+        let mut regex = String::from(if self.syntax.get_case_insensitive() {
+            "(?i-u:"
+        } else {
+            "(?-u:"
+        });
+        let mut empty = true;
+        for literal in literals {
+            if !empty {
+                regex.push('|');
+            }
+            empty = false;
+            for &byte in literal.as_ref() {
+                if byte.is_ascii_alphanumeric() {
+                    regex.push(byte as char);
+                } else {
+                    let _ = write!(regex, "\\x{:02X}", byte);
+                }
+            }
+        }
+        if empty {
+            // An empty set of literals can't match anything.
+            regex.push_str(r"[^\x00-\xFF]");
+        }
+        // The input only has to contain a literal, so anything may follow it.
+        regex.push_str(")(?s-u:.*)");
+
+        // The regex is built from escaped bytes, so none of the other syntax
+        // options apply, but the bytes need not be valid UTF-8. An alternation
+        // of literals is compiled into a trie, so this stays efficient for
+        // large sets.
+        let mut builder = self.clone();
+        builder.syntax = syntax::Config::new().utf8(false);
+        builder.build(&regex)
+    }
+
+    /// Compiles the given regexes into a [`PatternSet`] using this builder's
+    /// configuration, or returns an error if any of the regexes were invalid,
+    /// or the set was too big.
+    ///
+    /// [`PatternSet`]: ../struct.PatternSet.html
+    pub fn build_set<I, P>(&self, patterns: I) -> Result<PatternSet, Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<str>,
+    {
+        let patterns = patterns.into_iter().collect::<Vec<_>>();
+        let nfa = self.nfa(&patterns)?;
+        // Every pattern in the set must be able to match independently of the
+        // others, rather than the leftmost-first match taking precedence.
+        let automaton = dense::Builder::new()
+            .configure(self.dfa.clone().match_kind(MatchKind::All))
+            .build_from_nfa(&nfa)
+            .map_err(Error::dfa)?;
+        Ok(PatternSet {
+            automaton,
+            anchored: self.anchored,
+        })
+    }
+
+    /// Sets the maximum number of bytes of formatted output that are
+    /// buffered when extracting capture groups with
+    /// [`Pattern::debug_captures`] or [`Pattern::display_captures`].
+    ///
+    /// Extracting captures from a matching output longer than this returns
+    /// an error. By default, the limit is 64 KiB.
+    ///
+    /// [`Pattern::debug_captures`]: ../struct.Pattern.html#method.debug_captures
+    /// [`Pattern::display_captures`]: ../struct.Pattern.html#method.display_captures
+    pub fn capture_limit(&mut self, limit: usize) -> &mut Self {
+        self.capture_limit = limit;
+        self
+    }
+
+    /// Sets the maximum number of bytes of heap memory that may be used to
+    /// compile a regex into an NFA, or `None` for no limit.
+    ///
+    /// The NFA is compiled before the DFA is built from it, so this limit
+    /// rejects regexes that expand to very many NFA states, such as large
+    /// counted repetitions like `[a-z]{10000}`, before any time is spent
+    /// building the DFA. By default, there is no limit.
+    ///
+    /// Exceeding this limit returns an error whose kind is
+    /// [`ErrorKind::TooBig`].
+    ///
+    /// [`ErrorKind::TooBig`]: ../enum.ErrorKind.html#variant.TooBig
+    pub fn nfa_size_limit(&mut self, bytes: Option<usize>) -> &mut Self {
+        self.thompson = self.thompson.clone().nfa_size_limit(bytes);
+        self
+    }
+
+    /// Sets the maximum number of bytes of heap memory that may be used
+    /// while determinizing the NFA into a DFA, or `None` for no limit.
+    ///
+    /// Determinization may create a number of DFA states exponential in the
+    /// size of the regex, so this bounds both the memory and the time spent
+    /// building a pattern from an untrusted regex. Unlike the [DFA size
+    /// limit](#method.dfa_size_limit), it also includes the memory used by
+    /// the determinizer's intermediate state. By default, there is no limit.
+    ///
+    /// Exceeding this limit returns an error whose kind is
+    /// [`ErrorKind::TooBig`].
+    ///
+    /// [`ErrorKind::TooBig`]: ../enum.ErrorKind.html#variant.TooBig
+    pub fn determinize_size_limit(&mut self, bytes: Option<usize>) -> &mut Self {
+        self.dfa = self.dfa.clone().determinize_size_limit(bytes);
+        self
+    }
+
+    /// Sets the maximum number of bytes of heap memory that the compiled DFA
+    /// may use, or `None` for no limit.
+    ///
+    /// This is checked as the DFA is built, so building stops as soon as the
+    /// limit is exceeded. By default, there is no limit.
+    ///
+    /// Exceeding this limit returns an error whose kind is
+    /// [`ErrorKind::TooBig`].
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::{ErrorKind, PatternBuilder};
+    ///
+    /// let mut builder = PatternBuilder::new();
+    /// builder.unicode(false).dfa_size_limit(Some(10_000));
+    ///
+    /// assert!(builder.build("[a-z]+@[a-z]+").is_ok());
+    ///
+    /// let err = builder.build("[a-z]{5}x[a-z]{20}").unwrap_err();
+    /// assert_eq!(err.kind(), &ErrorKind::TooBig);
+    /// ```
+    ///
+    /// [`ErrorKind::TooBig`]: ../enum.ErrorKind.html#variant.TooBig
+    pub fn dfa_size_limit(&mut self, bytes: Option<usize>) -> &mut Self {
+        self.dfa = self.dfa.clone().dfa_size_limit(bytes);
+        self
+    }
+
+    /// Sets whether the pattern is anchored at the beginning of the input.
+    ///
+    /// An anchored pattern only matches an input if the first character or
+    /// byte of the input matches the pattern, like patterns constructed with
+    /// [`Pattern::new_anchored`]. By default, patterns are unanchored.
+    ///
+    /// [`Pattern::new_anchored`]: ../struct.Pattern.html#method.new_anchored
+    pub fn anchored(&mut self, yes: bool) -> &mut Self {
+        self.anchored = if yes { Anchored::Yes } else { Anchored::No };
+        self
+    }
+
+    /// Enables or disables case-insensitive matching.
+    ///
+    /// This is equivalent to the `i` flag. By default, it is disabled.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.syntax = self.syntax.case_insensitive(yes);
+        self
+    }
+
+    /// Enables or disables multi-line mode, in which `^` and `$` match at the
+    /// beginning and end of lines.
+    ///
+    /// This is equivalent to the `m` flag. By default, it is disabled.
+    pub fn multi_line(&mut self, yes: bool) -> &mut Self {
+        self.syntax = self.syntax.multi_line(yes);
+        self
+    }
+
+    /// Enables or disables whether `.` matches a `\n` character.
+    ///
+    /// This is equivalent to the `s` flag. By default, it is disabled.
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut Self {
+        self.syntax = self.syntax.dot_matches_new_line(yes);
+        self
+    }
+
+    /// Enables or disables Unicode mode.
+    ///
+    /// This is equivalent to the `u` flag. By default, it is enabled.
+    pub fn unicode(&mut self, yes: bool) -> &mut Self {
+        self.syntax = self.syntax.unicode(yes);
+        self
+    }
+
+    /// Sets whether the pattern is only permitted to match valid UTF-8.
+    ///
+    /// Disabling this allows patterns such as `(?-u:\xFF)` that match
+    /// arbitrary bytes, which is mostly useful when matching `io::Write`
+    /// output. By default, it is enabled.
+    pub fn utf8(&mut self, yes: bool) -> &mut Self {
+        self.syntax = self.syntax.utf8(yes);
+        self.thompson = self.thompson.clone().utf8(yes);
+        self
+    }
+
+    /// Enables or disables CRLF mode, in which `\r\n` is treated as a line
+    /// terminator in multi-line mode.
+    ///
+    /// This is equivalent to the `R` flag. By default, it is disabled.
+    pub fn crlf(&mut self, yes: bool) -> &mut Self {
+        self.syntax = self.syntax.crlf(yes);
+        self
+    }
+
+    /// Enables or disables verbose mode, in which whitespace is ignored and
+    /// `#` begins a comment.
+    ///
+    /// This is equivalent to the `x` flag. By default, it is disabled.
+    pub fn ignore_whitespace(&mut self, yes: bool) -> &mut Self {
+        self.syntax = self.syntax.ignore_whitespace(yes);
+        self
+    }
+
+    fn nfa<P: AsRef<str>>(&self, patterns: &[P]) -> Result<thompson::NFA, Error> {
+        // Each regex is parsed separately, so that syntax errors can refer to
+        // the regex they occurred in.
+        let hirs = patterns
+            .iter()
+            .map(|pattern| {
+                let pattern = pattern.as_ref();
+                syntax::parse_with(pattern, &self.syntax).map_err(|e| Error::syntax(pattern, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        thompson::Compiler::new()
+            // DFAs do not support capture groups, so the NFA doesn't need them.
+            .configure(
+                self.thompson
+                    .clone()
+                    .which_captures(thompson::WhichCaptures::None),
+            )
+            .build_many_from_hir(&hirs)
+            .map_err(Error::nfa)
+    }
+
+    fn capture_source(&self, pattern: &str) -> Arc<CaptureSource> {
+        Arc::new(CaptureSource::new(
+            pattern,
+            self.syntax,
+            self.thompson.clone(),
+            self.anchored,
+            self.capture_limit,
+        ))
+    }
+}
+
+impl Default for PatternBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ErrorKind;
+
+    #[test]
+    fn case_insensitive() {
+        let pat = PatternBuilder::new()
+            .case_insensitive(true)
+            .unicode(false)
+            .build("hello world")
+            .unwrap();
+        assert!(pat.display_matches(&"Hello World"));
+        assert!(pat.display_matches(&"HELLO WORLD"));
+    }
+
+    #[test]
+    fn dot_matches_new_line() {
+        let pat = PatternBuilder::new().build("a.b").unwrap();
+        assert!(!pat.display_matches(&"a\nb"));
+
+        let pat = PatternBuilder::new()
+            .dot_matches_new_line(true)
+            .build("a.b")
+            .unwrap();
+        assert!(pat.display_matches(&"a\nb"));
+    }
+
+    #[test]
+    fn multi_line() {
+        let pat = PatternBuilder::new().build("^world").unwrap();
+        assert!(!pat.display_matches(&"hello\nworld"));
+
+        let pat = PatternBuilder::new()
+            .multi_line(true)
+            .build("^world")
+            .unwrap();
+        assert!(pat.display_matches(&"hello\nworld"));
+    }
+
+    #[test]
+    fn ignore_whitespace() {
+        let pat = PatternBuilder::new()
+            .ignore_whitespace(true)
+            .build("hello \\  world # a comment")
+            .unwrap();
+        assert!(pat.display_matches(&"hello world"));
+    }
+
+    #[test]
+    fn unicode_and_utf8() {
+        assert!(PatternBuilder::new().build(r"(?-u:\xFF)").is_err());
+
+        let pat = PatternBuilder::new()
+            .unicode(false)
+            .utf8(false)
+            .build(r"\xFF")
+            .unwrap();
+        assert!(pat.read_matches(&[0xFF][..]).unwrap());
+    }
+
+    #[test]
+    fn build_sparse() {
+        let pat = PatternBuilder::new()
+            .anchored(true)
+            .dot_matches_new_line(true)
+            .build_sparse("a.+b")
+            .unwrap();
+        assert!(pat.display_matches(&"a\nb"));
+        assert!(!pat.display_matches(&"ffa\nb"));
+    }
+
+    #[test]
+    #[cfg(feature = "hybrid")]
+    fn build_lazy() {
+        let pat = PatternBuilder::new()
+            .anchored(true)
+            .dot_matches_new_line(true)
+            .build_lazy("a.+b")
+            .unwrap();
+        assert!(pat.display_matches(&"a\nb"));
+        assert!(!pat.display_matches(&"ffa\nb"));
+    }
+
+    #[test]
+    fn anchored() {
+        let pat = PatternBuilder::new().anchored(true).build("a+b").unwrap();
+        assert!(pat.display_matches(&"aab"));
+        assert!(!pat.display_matches(&"ffaab"));
+
+        let pat = PatternBuilder::new().anchored(false).build("a+b").unwrap();
+        assert!(pat.display_matches(&"ffaab"));
+    }
+
+    #[test]
+    fn size_limits() {
+        let mut builder = PatternBuilder::new();
+        builder.unicode(false).determinize_size_limit(Some(10_000));
+        assert!(builder.build("a+b").is_ok());
+        let err = builder.build("[ab]*a[ab]{12}").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TooBig);
+
+        let err = PatternBuilder::new()
+            .unicode(false)
+            .dfa_size_limit(Some(1_000))
+            .build_set(["foo[a-z]{10}", "bar"])
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TooBig);
+
+        let err = PatternBuilder::new()
+            .nfa_size_limit(Some(1_000))
+            .build_literals((0..1_000).map(|i| i.to_string()))
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TooBig);
+    }
+
+    #[test]
+    #[cfg(feature = "hybrid")]
+    fn build_lazy_size_limit() {
+        let err = PatternBuilder::new()
+            .nfa_size_limit(Some(1_000))
+            .build_lazy("[a-z]{1000}")
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TooBig);
+    }
+
+    #[test]
+    fn build_literals() {
+        let pat = PatternBuilder::new()
+            .build_literals(["a.b", "c*", "(d)"])
+            .unwrap();
+        assert!(pat.display_matches(&"xxa.byy"));
+        assert!(pat.display_matches(&"c*"));
+        assert!(pat.display_matches(&"((d))"));
+        assert!(!pat.display_matches(&"axb"));
+        assert!(!pat.display_matches(&"cc"));
+        assert!(!pat.display_matches(&"d"));
+        assert!(!pat.display_matches(&"A.B"));
+    }
+
+    #[test]
+    fn build_literals_case_insensitive() {
+        let pat = PatternBuilder::new()
+            .case_insensitive(true)
+            .build_literals(["Hello", "wörld"])
+            .unwrap();
+        assert!(pat.display_matches(&"oh, HELLO there"));
+        assert!(pat.display_matches(&"WöRLD"));
+        // Only ASCII letters are case-insensitive.
+        assert!(!pat.display_matches(&"WÖRLD"));
+    }
+
+    #[test]
+    fn build_literals_anchored() {
+        let pat = PatternBuilder::new()
+            .anchored(true)
+            .build_literals(["foo", "bar"])
+            .unwrap();
+        assert!(pat.display_matches(&"foobar"));
+        assert!(pat.display_matches(&"bar"));
+        assert!(!pat.display_matches(&"xfoo"));
+    }
+
+    #[test]
+    fn build_literals_bytes() {
+        let pat = PatternBuilder::new()
+            .build_literals([&b"\xff\x00"[..], b" \\n"])
+            .unwrap();
+        assert!(pat.read_matches(&b"a\xff\x00b"[..]).unwrap());
+        assert!(pat.read_matches(&b"a \\n"[..]).unwrap());
+        assert!(!pat.read_matches(&b"\xff"[..]).unwrap());
+    }
+
+    #[test]
+    fn build_literals_empty() {
+        let pat = PatternBuilder::new()
+            .build_literals(Vec::<&str>::new())
+            .unwrap();
+        assert!(!pat.display_matches(&""));
+        assert!(!pat.display_matches(&"anything"));
+
+        let pat = PatternBuilder::new().build_literals([""]).unwrap();
+        assert!(pat.display_matches(&""));
+        assert!(pat.display_matches(&"anything"));
+    }
+
+    #[test]
+    fn build_literals_many() {
+        let literals: Vec<String> = (0..5_000).map(|i| format!("bot-{}/", i)).collect();
+        let pat = PatternBuilder::new().build_literals(&literals).unwrap();
+        assert!(pat.display_matches(&"Mozilla/5.0 bot-4999/1.0"));
+        assert!(pat.display_matches(&"bot-0/"));
+        assert!(!pat.display_matches(&"bot-5000/"));
+        assert!(!pat.display_matches(&"bot-12"));
+    }
+
+    #[test]
+    fn escape() {
+        let pat = Pattern::new_anchored(&Pattern::escape("a+b (c)?")).unwrap();
+        assert!(pat.display_matches(&"a+b (c)?"));
+        assert!(!pat.display_matches(&"aab c"));
+    }
+}
@@ -0,0 +1,436 @@
+use crate::{Backend, Matcher, Pattern};
+use alloc::borrow::ToOwned;
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{error::Error, sync::OnceLock};
+
+use regex_automata::dfa::onepass;
+use regex_automata::nfa::thompson::{self, pikevm::PikeVM};
+use regex_automata::util::{captures::GroupInfo, syntax};
+use regex_automata::{Anchored, Input, PatternID};
+use regex_syntax::hir::{Hir, Look};
+
+/// The default maximum number of bytes of formatted output that are buffered
+/// while extracting captures.
+pub(crate) const DEFAULT_CAPTURE_LIMIT: usize = 64 * 1024;
+
+/// The groups captured by a match of a [`Pattern`].
+///
+/// This is returned by [`Pattern::debug_captures`] and
+/// [`Pattern::display_captures`]. It only retains the matched part of the
+/// formatted output, rather than all of it.
+///
+/// [`Pattern`]: ../struct.Pattern.html
+/// [`Pattern::debug_captures`]: ../struct.Pattern.html#method.debug_captures
+/// [`Pattern::display_captures`]: ../struct.Pattern.html#method.display_captures
+#[derive(Debug, Clone)]
+pub struct Captures {
+    /// The bytes of the overall match, which contain every group.
+    matched: Vec<u8>,
+    /// The start and end offsets of each group in the formatted output.
+    spans: Vec<Option<(usize, usize)>>,
+    group_info: GroupInfo,
+}
+
+/// An error returned when the captures of a [`Pattern`] can't be extracted.
+///
+/// [`Pattern`]: ../struct.Pattern.html
+#[derive(Debug)]
+pub struct CapturesError {
+    kind: ErrorKind,
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    TooLong { limit: usize },
+    NoSource,
+    Build(thompson::BuildError),
+}
+
+/// What's needed to build an engine that can report the positions of capture
+/// groups, which the DFA used for matching can't.
+///
+/// Building the engine requires parsing the pattern again, so this is only
+/// done the first time captures are requested. Without the `std` feature,
+/// there's no way to initialize the engine lazily from multiple threads, so
+/// it is built every time instead.
+#[derive(Debug)]
+pub(crate) struct CaptureSource {
+    pattern: String,
+    syntax: syntax::Config,
+    thompson: thompson::Config,
+    anchored: Anchored,
+    limit: usize,
+    #[cfg(feature = "std")]
+    engine: OnceLock<Result<Engine, thompson::BuildError>>,
+}
+
+#[derive(Debug)]
+enum Engine {
+    OnePass(Box<onepass::DFA>),
+    PikeVM(PikeVM),
+}
+
+/// Buffers formatted output for extracting captures, while matching it with
+/// the pattern's DFA so that formatting stops as soon as it can't match.
+struct CaptureWriter<'a, A: Backend> {
+    matcher: Matcher<&'a A>,
+    buf: Vec<u8>,
+    limit: usize,
+    overflowed: bool,
+}
+
+// === impl Pattern ===
+
+impl<A: Backend> Pattern<A> {
+    /// Returns the groups captured when this pattern matches the formatted
+    /// output of the given type implementing `fmt::Debug`, `None` if it does
+    /// not match, or an error if the captures could not be extracted.
+    ///
+    /// The pattern matches in the same way as [`debug_matches`]: the match
+    /// must end at the end of the output, and if several matches do, the
+    /// groups of the one that begins first are returned.
+    ///
+    /// Unlike `debug_matches`, the output has to be buffered, so this returns
+    /// an error if a matching output is longer than the limit set by
+    /// [`PatternBuilder::capture_limit`] (64 KiB by default). Output that
+    /// can't match is not buffered past the point where that is known, and
+    /// never causes an error. An error is also returned for patterns that were
+    /// not compiled from a regex in this process, such as those loaded with
+    /// [`Pattern::from_bytes`], since the regex is needed to find the groups.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// #[derive(Debug)]
+    /// struct Request {
+    ///     id: u64,
+    ///     path: &'static str,
+    /// }
+    ///
+    /// let pattern = Pattern::new_anchored(r#"Request \{ id: (?P<id>[0-9]+), path: "/api/.*" \}"#)
+    ///     .expect("regex is not invalid");
+    ///
+    /// let request = Request { id: 42, path: "/api/users" };
+    /// let captures = pattern
+    ///     .debug_captures(&request)
+    ///     .expect("output is short")
+    ///     .expect("pattern matches");
+    /// assert_eq!(captures.name("id"), Some(&b"42"[..]));
+    ///
+    /// let request = Request { id: 7, path: "/index.html" };
+    /// assert!(pattern.debug_captures(&request).unwrap().is_none());
+    /// ```
+    ///
+    /// [`debug_matches`]: #method.debug_matches
+    /// [`PatternBuilder::capture_limit`]: ../struct.PatternBuilder.html#method.capture_limit
+    /// [`Pattern::from_bytes`]: #method.from_bytes
+    pub fn debug_captures(&self, d: &impl fmt::Debug) -> Result<Option<Captures>, CapturesError> {
+        use core::fmt::Write;
+        self.captures_with(|w| write!(w, "{:?}", d))
+    }
+
+    /// Returns the groups captured when this pattern matches the formatted
+    /// output of the given type implementing `fmt::Display`, `None` if it
+    /// does not match, or an error if the captures could not be extracted.
+    ///
+    /// See [`debug_captures`] for details.
+    ///
+    /// [`debug_captures`]: #method.debug_captures
+    pub fn display_captures(
+        &self,
+        d: &impl fmt::Display,
+    ) -> Result<Option<Captures>, CapturesError> {
+        use core::fmt::Write;
+        self.captures_with(|w| write!(w, "{}", d))
+    }
+
+    fn captures_with(
+        &self,
+        format: impl FnOnce(&mut CaptureWriter<'_, A>) -> fmt::Result,
+    ) -> Result<Option<Captures>, CapturesError> {
+        let source = self.captures.as_ref().ok_or(ErrorKind::NoSource)?;
+        let mut writer = CaptureWriter {
+            matcher: self.matcher(),
+            buf: Vec::new(),
+            limit: source.limit,
+            overflowed: false,
+        };
+        // An error means that the output can't match, so the rest of it can
+        // be ignored.
+        let _ = format(&mut writer);
+        if !writer.matcher.is_matched() {
+            return Ok(None);
+        }
+        if writer.overflowed {
+            return Err(ErrorKind::TooLong {
+                limit: source.limit,
+            }
+            .into());
+        }
+        source.captures(&writer.buf)
+    }
+}
+
+// === impl Captures ===
+
+impl Captures {
+    /// Returns the bytes captured by the group with the given index, or
+    /// `None` if the group did not participate in the match or does not
+    /// exist.
+    ///
+    /// Group 0 is the overall match, and the groups in the pattern are
+    /// numbered from 1 in the order of their opening parentheses.
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        let (start, end) = self.span(index)?;
+        let (offset, _) = self.span(0)?;
+        Some(&self.matched[start - offset..end - offset])
+    }
+
+    /// Returns the bytes captured by the group with the given name, or
+    /// `None` if the group did not participate in the match or does not
+    /// exist.
+    pub fn name(&self, name: &str) -> Option<&[u8]> {
+        self.get(self.group_info.to_index(PatternID::ZERO, name)?)
+    }
+
+    /// Returns the start and end byte offsets of the group with the given
+    /// index in the formatted output, or `None` if the group did not
+    /// participate in the match or does not exist.
+    pub fn span(&self, index: usize) -> Option<(usize, usize)> {
+        self.spans.get(index).copied().flatten()
+    }
+}
+
+// === impl CaptureSource ===
+
+impl CaptureSource {
+    pub(crate) fn new(
+        pattern: &str,
+        syntax: syntax::Config,
+        thompson: thompson::Config,
+        anchored: Anchored,
+        limit: usize,
+    ) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            syntax,
+            thompson,
+            anchored,
+            limit,
+            #[cfg(feature = "std")]
+            engine: OnceLock::new(),
+        }
+    }
+
+    fn captures(&self, haystack: &[u8]) -> Result<Option<Captures>, CapturesError> {
+        #[cfg(feature = "std")]
+        let engine = self
+            .engine
+            .get_or_init(|| self.build())
+            .as_ref()
+            .map_err(|e| ErrorKind::Build(e.clone()))?;
+        #[cfg(not(feature = "std"))]
+        let engine = &self.build().map_err(ErrorKind::Build)?;
+        let input = Input::new(haystack).anchored(self.anchored);
+        let caps = match engine {
+            Engine::OnePass(dfa) => {
+                let mut caps = dfa.create_captures();
+                dfa.captures(&mut dfa.create_cache(), input, &mut caps);
+                caps
+            }
+            Engine::PikeVM(vm) => {
+                let mut caps = vm.create_captures();
+                vm.captures(&mut vm.create_cache(), input, &mut caps);
+                caps
+            }
+        };
+        let whole = match caps.get_group(0) {
+            Some(span) => span,
+            None => return Ok(None),
+        };
+        let spans = (0..caps.group_len())
+            .map(|i| caps.get_group(i).map(|span| (span.start, span.end)))
+            .collect();
+        Ok(Some(Captures {
+            matched: haystack[whole.range()].to_vec(),
+            spans,
+            group_info: caps.group_info().clone(),
+        }))
+    }
+
+    fn build(&self) -> Result<Engine, thompson::BuildError> {
+        let hir = syntax::parse_with(&self.pattern, &self.syntax)
+            .expect("pattern was already parsed successfully when it was built");
+        // Patterns only match if a match ends at the end of the input, so
+        // require that, rather than finding the leftmost-first match that
+        // ends anywhere.
+        let hir = Hir::concat(vec![hir, Hir::look(Look::End)]);
+        let nfa = thompson::Compiler::new()
+            .configure(self.thompson.clone())
+            .build_from_hir(&hir)?;
+        // The one-pass DFA is faster, but only supports anchored searches,
+        // and only some patterns.
+        if self.anchored == Anchored::Yes {
+            if let Ok(dfa) = onepass::Builder::new().build_from_nfa(nfa.clone()) {
+                return Ok(Engine::OnePass(Box::new(dfa)));
+            }
+        }
+        PikeVM::builder().build_from_nfa(nfa).map(Engine::PikeVM)
+    }
+}
+
+// === impl CaptureWriter ===
+
+impl<A: Backend> fmt::Write for CaptureWriter<'_, A> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.matcher.advance_bytes(s.as_bytes());
+        if self.matcher.automaton.is_dead_state(self.matcher.state) {
+            return Err(fmt::Error);
+        }
+        // Keep matching after the limit is reached, since output that turns
+        // out not to match doesn't need to be buffered at all.
+        if !self.overflowed {
+            if self.buf.len() + s.len() > self.limit {
+                self.overflowed = true;
+                self.buf = Vec::new();
+            } else {
+                self.buf.extend_from_slice(s.as_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+// === impl CapturesError ===
+
+impl From<ErrorKind> for CapturesError {
+    fn from(kind: ErrorKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl fmt::Display for CapturesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::TooLong { limit } => write!(
+                f,
+                "formatted output is longer than the capture limit of {} bytes",
+                limit
+            ),
+            ErrorKind::NoSource => {
+                f.write_str("pattern was not compiled from a regex, so it has no capture groups")
+            }
+            ErrorKind::Build(ref e) => write!(f, "failed to build capture engine: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for CapturesError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            ErrorKind::Build(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PatternBuilder;
+
+    struct Str<'a>(&'a str);
+
+    impl fmt::Debug for Str<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    /// Formats a string one character at a time.
+    struct Chars<'a>(&'a str);
+
+    impl fmt::Display for Chars<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            for c in self.0.chars() {
+                fmt::Write::write_char(f, c)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn groups(captures: &Captures, len: usize) -> Vec<Option<&str>> {
+        (0..len)
+            .map(|i| captures.get(i).map(|g| std::str::from_utf8(g).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn anchored_captures() {
+        let pat = Pattern::new_anchored(r"([a-z]+)=([0-9]+)?(x)?").unwrap();
+        let caps = pat.debug_captures(&Str("id=42")).unwrap().unwrap();
+        assert_eq!(
+            groups(&caps, 5),
+            vec![Some("id=42"), Some("id"), Some("42"), None, None]
+        );
+        assert_eq!(caps.span(2), Some((3, 5)));
+        assert!(pat.debug_captures(&Str(" id=42")).unwrap().is_none());
+    }
+
+    #[test]
+    fn unanchored_captures_end_at_end_of_input() {
+        let pat = Pattern::new(r"(a+)(bc|b)").unwrap();
+        let caps = pat.display_captures(&Chars("xy aabc")).unwrap().unwrap();
+        assert_eq!(groups(&caps, 3), vec![Some("aabc"), Some("aa"), Some("bc")]);
+        assert_eq!(caps.span(0), Some((3, 7)));
+        assert!(pat.display_captures(&Chars("aabcd")).unwrap().is_none());
+    }
+
+    #[test]
+    fn named_groups() {
+        let pat = Pattern::new(r"user=(?P<user>[a-z]+) id=(?P<id>[0-9]+)").unwrap();
+        let caps = pat
+            .display_captures(&"login: user=alice id=7")
+            .unwrap()
+            .unwrap();
+        assert_eq!(caps.name("user"), Some(&b"alice"[..]));
+        assert_eq!(caps.name("id"), Some(&b"7"[..]));
+        assert_eq!(caps.name("nope"), None);
+    }
+
+    #[test]
+    fn capture_limit() {
+        let pat = PatternBuilder::new()
+            .anchored(true)
+            .capture_limit(8)
+            .build("([a-z]+)[0-9]*")
+            .unwrap();
+        assert!(pat.display_captures(&Chars("abc123")).unwrap().is_some());
+        assert!(pat.display_captures(&Chars("abcdef123")).is_err());
+        // Output that can't match is never an error.
+        assert!(pat.display_captures(&Chars("123abcdef")).unwrap().is_none());
+    }
+
+    #[test]
+    fn other_backends() {
+        let pat = Pattern::new_sparse("a(b+)").unwrap();
+        let caps = pat.display_captures(&"abbb").unwrap().unwrap();
+        assert_eq!(caps.get(1), Some(&b"bbb"[..]));
+
+        let pat = Pattern::new_anchored("a(b+)").unwrap().into_shared();
+        let caps = pat.clone().display_captures(&"abbb").unwrap().unwrap();
+        assert_eq!(caps.get(1), Some(&b"bbb"[..]));
+    }
+
+    #[test]
+    fn deserialized_pattern_has_no_captures() {
+        let pat = Pattern::new("a(b+)").unwrap();
+        let (bytes, padding) = pat.to_bytes_native_endian();
+        let (loaded, _) = Pattern::from_bytes(&bytes[padding..]).unwrap();
+        assert!(loaded.display_captures(&"abbb").is_err());
+    }
+}
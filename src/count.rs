@@ -0,0 +1,332 @@
+use crate::search::{Piece, Search};
+use crate::Pattern;
+#[cfg(feature = "std")]
+use crate::READ_BUF_LEN;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use regex_automata::dfa::dense::DFA;
+use regex_automata::dfa::Automaton;
+use regex_automata::util::primitives::StateID;
+use regex_automata::util::start;
+use regex_automata::Anchored;
+
+/// Counts the matches of a [`Pattern`] in input provided incrementally via
+/// `fmt::Write` or `io::Write`.
+///
+/// A `CountingMatcher` is returned by [`Pattern::counting_matcher`], which
+/// counts non-overlapping matches, or by
+/// [`Pattern::overlapping_counting_matcher`], which counts a match for every
+/// position at which one begins, even if it overlaps another match. In both
+/// cases, empty matches are not counted.
+///
+/// For example:
+/// ```
+/// use matchers::Pattern;
+/// use std::fmt::Write;
+///
+/// let pattern = Pattern::new("aba").expect("regex is not invalid");
+///
+/// let mut matcher = pattern.counting_matcher();
+/// write!(matcher, "ababa, {}", "aba").unwrap();
+/// assert_eq!(matcher.count(), 2);
+///
+/// let mut matcher = pattern.overlapping_counting_matcher();
+/// write!(matcher, "ababa, {}", "aba").unwrap();
+/// assert_eq!(matcher.count(), 3);
+/// ```
+///
+/// [`Pattern`]: ../struct.Pattern.html
+/// [`Pattern::counting_matcher`]: ../struct.Pattern.html#method.counting_matcher
+/// [`Pattern::overlapping_counting_matcher`]: ../struct.Pattern.html#method.overlapping_counting_matcher
+#[derive(Debug, Clone)]
+pub struct CountingMatcher<A = DFA<Vec<u32>>> {
+    inner: Counter<A>,
+}
+
+#[derive(Debug, Clone)]
+enum Counter<A> {
+    NonOverlapping { search: Search<A>, count: u64 },
+    Overlapping(Overlapping<A>),
+}
+
+/// Counts overlapping matches by running an anchored search from every
+/// position in the input.
+///
+/// A search is finished as soon as it finds a match, since only one match is
+/// counted per starting position. Searches that are in the same state will
+/// see the same input from then on, so they are merged, and counted once for
+/// each position they started at.
+#[derive(Debug, Clone)]
+struct Overlapping<A> {
+    automaton: A,
+    anchored: bool,
+    /// The number of unfinished searches in each state.
+    searches: BTreeMap<StateID, u64>,
+    next: BTreeMap<StateID, u64>,
+    pos: u64,
+    look_behind: Option<u8>,
+    count: u64,
+}
+
+// === impl Pattern ===
+
+impl<A: Automaton> Pattern<A> {
+    /// Returns the number of non-overlapping matches of this pattern in the
+    /// data read from the provided `io::Read` stream, or an `io::Error` if an
+    /// error occurred reading from the stream.
+    ///
+    /// Matches are found in the same way as by [`Pattern::find_iter_read`].
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let pattern = Pattern::new("error").expect("regex is not invalid");
+    /// let log = "info: ok\nerror: oh no\nwarn: hmm\nerror: not again\n";
+    /// assert_eq!(pattern.count_read(log.as_bytes()).unwrap(), 2);
+    /// ```
+    ///
+    /// [`Pattern::find_iter_read`]: #method.find_iter_read
+    #[cfg(feature = "std")]
+    pub fn count_read(&self, mut io: impl io::Read) -> io::Result<u64> {
+        let mut matcher = self.counting_matcher();
+        let mut buf = [0u8; READ_BUF_LEN];
+        loop {
+            let n = match io.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            matcher.push(&buf[..n]);
+        }
+        Ok(matcher.count())
+    }
+
+    /// Returns a [`CountingMatcher`] that counts the non-overlapping matches
+    /// of this pattern in its input.
+    ///
+    /// [`CountingMatcher`]: ../struct.CountingMatcher.html
+    pub fn counting_matcher(&self) -> CountingMatcher<&'_ A> {
+        CountingMatcher {
+            inner: Counter::NonOverlapping {
+                search: Search::new(&self.automaton, self.anchored),
+                count: 0,
+            },
+        }
+    }
+
+    /// Returns a [`CountingMatcher`] that counts the matches of this pattern
+    /// in its input that begin at each position, including matches that
+    /// overlap each other.
+    ///
+    /// [`CountingMatcher`]: ../struct.CountingMatcher.html
+    pub fn overlapping_counting_matcher(&self) -> CountingMatcher<&'_ A> {
+        CountingMatcher {
+            inner: Counter::Overlapping(Overlapping {
+                automaton: &self.automaton,
+                anchored: self.anchored == Anchored::Yes,
+                searches: BTreeMap::new(),
+                next: BTreeMap::new(),
+                pos: 0,
+                look_behind: None,
+                count: 0,
+            }),
+        }
+    }
+}
+
+// === impl CountingMatcher ===
+
+impl<A: Automaton + Clone> CountingMatcher<A> {
+    /// Returns the number of matches in the input provided so far, including
+    /// any matches that end at the end of the input.
+    pub fn count(&self) -> u64 {
+        match self.inner {
+            Counter::NonOverlapping { ref search, count } => {
+                // Matches that may still be extended by more input can only be
+                // resolved by ending the input, so do that to a copy.
+                let mut search = search.clone();
+                search.finish();
+                count + count_matches(&mut search)
+            }
+            Counter::Overlapping(ref overlapping) => overlapping.count(),
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        match self.inner {
+            Counter::NonOverlapping {
+                ref mut search,
+                ref mut count,
+            } => {
+                search.push(bytes);
+                *count += count_matches(search);
+            }
+            Counter::Overlapping(ref mut overlapping) => {
+                for &byte in bytes {
+                    overlapping.advance(byte);
+                }
+            }
+        }
+    }
+}
+
+fn count_matches<A: Automaton>(search: &mut Search<A>) -> u64 {
+    let mut count = 0;
+    let _ = search.drain(|piece| {
+        if let Piece::Match { .. } = piece {
+            count += 1;
+        }
+        Ok::<_, ()>(())
+    });
+    count
+}
+
+impl<A: Automaton + Clone> fmt::Write for CountingMatcher<A> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Automaton + Clone> io::Write for CountingMatcher<A> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, io::Error> {
+        self.push(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+// === impl Overlapping ===
+
+impl<A: Automaton> Overlapping<A> {
+    fn advance(&mut self, byte: u8) {
+        for (&state, &searches) in &self.searches {
+            // It's safe to call `next_state_unchecked` since every state was
+            // produced by the same DFA, which can only be constructed by a
+            // `Pattern`.
+            let next = unsafe { self.automaton.next_state_unchecked(state, byte) };
+            // Match states are delayed by one byte, so this match ended before
+            // the byte we just consumed.
+            if self.automaton.is_match_state(next) {
+                self.count += searches;
+            } else if !self.automaton.is_dead_state(next) {
+                *self.next.entry(next).or_insert(0) += searches;
+            }
+        }
+
+        // Start a new search at this position. If it enters a match state
+        // immediately, that's an empty match, which isn't counted.
+        if !self.anchored || self.pos == 0 {
+            let config = start::Config::new()
+                .anchored(Anchored::Yes)
+                .look_behind(self.look_behind);
+            let start = self
+                .automaton
+                .start_state(&config)
+                .expect("pattern DFAs support anchored searches and have no quit bytes");
+            let next = unsafe { self.automaton.next_state_unchecked(start, byte) };
+            if !self.automaton.is_dead_state(next) {
+                *self.next.entry(next).or_insert(0) += 1;
+            }
+        }
+
+        core::mem::swap(&mut self.searches, &mut self.next);
+        self.next.clear();
+        self.pos += 1;
+        self.look_behind = Some(byte);
+    }
+
+    fn count(&self) -> u64 {
+        let at_end: u64 = self
+            .searches
+            .iter()
+            .filter(|&(&state, _)| {
+                let eoi = self.automaton.next_eoi_state(state);
+                self.automaton.is_match_state(eoi)
+            })
+            .map(|(_, &searches)| searches)
+            .sum();
+        self.count + at_end
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::fmt::Write;
+
+    fn count(pattern: &Pattern, chunks: &[&str]) -> (u64, u64) {
+        let mut matcher = pattern.counting_matcher();
+        let mut overlapping = pattern.overlapping_counting_matcher();
+        for chunk in chunks {
+            matcher.write_str(chunk).unwrap();
+            overlapping.write_str(chunk).unwrap();
+        }
+        (matcher.count(), overlapping.count())
+    }
+
+    #[test]
+    fn counts_matches() {
+        let pat = Pattern::new("aa").unwrap();
+        assert_eq!(count(&pat, &["aaaa"]), (2, 3));
+        assert_eq!(count(&pat, &["a", "a", "a", "a", "a"]), (2, 4));
+        assert_eq!(count(&pat, &["bab"]), (0, 0));
+        assert_eq!(count(&pat, &[]), (0, 0));
+    }
+
+    #[test]
+    fn counts_matches_of_different_lengths() {
+        let pat = Pattern::new("a+b").unwrap();
+        assert_eq!(count(&pat, &["aab ab aaa"]), (2, 3));
+        assert_eq!(count(&pat, &["aab ab aaa", "b"]), (3, 6));
+    }
+
+    #[test]
+    fn count_is_idempotent() {
+        let pat = Pattern::new("[0-9]+").unwrap();
+        let mut matcher = pat.counting_matcher();
+        matcher.write_str("12 34").unwrap();
+        assert_eq!(matcher.count(), 2);
+        assert_eq!(matcher.count(), 2);
+        // The second match is extended, not counted again.
+        matcher.write_str("56 7").unwrap();
+        assert_eq!(matcher.count(), 3);
+    }
+
+    #[test]
+    fn empty_matches_are_not_counted() {
+        let pat = Pattern::new("a*").unwrap();
+        assert_eq!(count(&pat, &["baab"]), (1, 2));
+    }
+
+    #[test]
+    fn anchored() {
+        let pat = Pattern::new_anchored("ab").unwrap();
+        assert_eq!(count(&pat, &["abab"]), (1, 1));
+        assert_eq!(count(&pat, &["bab"]), (0, 0));
+    }
+
+    #[test]
+    fn look_behind() {
+        let pat = Pattern::builder().unicode(false).build(r"\bab").unwrap();
+        assert_eq!(count(&pat, &["ab a", "b aab"]), (2, 2));
+    }
+
+    #[test]
+    fn count_read() {
+        let pat = Pattern::new("needle").unwrap();
+        let mut input = vec![b'.'; READ_BUF_LEN - 2];
+        input.extend_from_slice(b"needle needle");
+        assert_eq!(pat.count_read(&input[..]).unwrap(), 2);
+    }
+}
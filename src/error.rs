@@ -0,0 +1,350 @@
+use alloc::string::{String, ToString};
+use core::fmt::{self, Write};
+use core::ops::Range;
+use regex_automata::dfa::dense;
+#[cfg(feature = "hybrid")]
+use regex_automata::hybrid;
+use regex_automata::nfa::thompson;
+use regex_syntax::{ast, hir};
+
+/// An error that occurred while compiling a [`Pattern`] or [`PatternSet`].
+///
+/// The [kind](#method.kind) of the error distinguishes regexes that are
+/// syntactically invalid from those that use features DFAs can't match, and
+/// from those that exceeded one of the size limits configured on the
+/// [`PatternBuilder`], so that untrusted regexes which are too expensive to
+/// compile can be rejected separately.
+///
+/// When the error can be attributed to a part of the regex, the error's
+/// `Display` implementation renders the offending line of the regex with
+/// carets underneath that part.
+///
+/// For example:
+/// ```
+/// use matchers::{ErrorKind, Pattern, PatternBuilder};
+///
+/// let err = Pattern::new("foo(bar|baz").unwrap_err();
+/// assert_eq!(
+///     err.kind(),
+///     &ErrorKind::Syntax { span: 3..4, message: "unclosed group".to_string() },
+/// );
+/// assert_eq!(err.to_string(), "unclosed group\n    foo(bar|baz\n       ^");
+///
+/// let err = PatternBuilder::new()
+///     .unicode(false)
+///     .dfa_size_limit(Some(10_000))
+///     .build("[a-z]{5}x[a-z]{20}")
+///     .unwrap_err();
+/// assert_eq!(err.kind(), &ErrorKind::TooBig);
+/// ```
+///
+/// [`Pattern`]: ../struct.Pattern.html
+/// [`PatternSet`]: ../struct.PatternSet.html
+/// [`PatternBuilder`]: ../struct.PatternBuilder.html
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    /// The regex that the error's span refers to.
+    pattern: Option<String>,
+    source: Source,
+}
+
+/// The kind of an [`Error`].
+///
+/// [`Error`]: struct.Error.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The regex was syntactically invalid.
+    Syntax {
+        /// The range of bytes in the regex that the error refers to.
+        span: Range<usize>,
+        /// A description of the error.
+        message: String,
+    },
+    /// The regex used a feature that patterns do not support, such as
+    /// look-around, backreferences or Unicode word boundaries.
+    Unsupported {
+        /// The range of bytes in the regex that uses the feature, if it is
+        /// known.
+        span: Option<Range<usize>>,
+        /// A description of the error.
+        message: String,
+    },
+    /// Compiling the regex exceeded one of the configured size limits.
+    ///
+    /// See [`PatternBuilder::nfa_size_limit`],
+    /// [`PatternBuilder::determinize_size_limit`] and
+    /// [`PatternBuilder::dfa_size_limit`].
+    ///
+    /// [`PatternBuilder::nfa_size_limit`]: ../struct.PatternBuilder.html#method.nfa_size_limit
+    /// [`PatternBuilder::determinize_size_limit`]: ../struct.PatternBuilder.html#method.determinize_size_limit
+    /// [`PatternBuilder::dfa_size_limit`]: ../struct.PatternBuilder.html#method.dfa_size_limit
+    TooBig,
+}
+
+#[derive(Debug)]
+enum Source {
+    Syntax(regex_syntax::Error),
+    Nfa(thompson::BuildError),
+    Dfa(dense::BuildError),
+    #[cfg(feature = "hybrid")]
+    Lazy(hybrid::BuildError),
+}
+
+// === impl Error ===
+
+impl Error {
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub(crate) fn syntax(pattern: &str, error: regex_syntax::Error) -> Self {
+        let (span, message, unsupported) = match error {
+            regex_syntax::Error::Parse(ref e) => (
+                range(e.span()),
+                e.kind().to_string(),
+                matches!(
+                    e.kind(),
+                    ast::ErrorKind::UnsupportedLookAround
+                        | ast::ErrorKind::UnsupportedBackreference
+                ),
+            ),
+            // Unicode classes that are unavailable without the `unicode`
+            // feature flag are valid syntax, but can't be compiled.
+            regex_syntax::Error::Translate(ref e) => (
+                range(e.span()),
+                e.kind().to_string(),
+                matches!(
+                    e.kind(),
+                    hir::ErrorKind::UnicodePerlClassNotFound
+                        | hir::ErrorKind::UnicodeCaseUnavailable
+                ),
+            ),
+            ref e => (0..pattern.len(), e.to_string(), false),
+        };
+        let kind = if unsupported {
+            ErrorKind::Unsupported {
+                span: Some(span),
+                message,
+            }
+        } else {
+            ErrorKind::Syntax { span, message }
+        };
+        Self {
+            kind,
+            pattern: Some(pattern.to_string()),
+            source: Source::Syntax(error),
+        }
+    }
+
+    pub(crate) fn nfa(error: thompson::BuildError) -> Self {
+        let kind = if error.size_limit().is_some() {
+            ErrorKind::TooBig
+        } else {
+            unsupported(&error)
+        };
+        Self {
+            kind,
+            pattern: None,
+            source: Source::Nfa(error),
+        }
+    }
+
+    pub(crate) fn dfa(error: dense::BuildError) -> Self {
+        let kind = if error.is_size_limit_exceeded() {
+            ErrorKind::TooBig
+        } else {
+            unsupported(&error)
+        };
+        Self {
+            kind,
+            pattern: None,
+            source: Source::Dfa(error),
+        }
+    }
+
+    #[cfg(feature = "hybrid")]
+    pub(crate) fn lazy(error: hybrid::BuildError) -> Self {
+        Self {
+            kind: unsupported(&error),
+            pattern: None,
+            source: Source::Lazy(error),
+        }
+    }
+
+    fn span(&self) -> Option<&Range<usize>> {
+        match self.kind {
+            ErrorKind::Syntax { ref span, .. } => Some(span),
+            ErrorKind::Unsupported { ref span, .. } => span.as_ref(),
+            ErrorKind::TooBig => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Syntax { ref message, .. } | ErrorKind::Unsupported { ref message, .. } => {
+                f.write_str(message)?
+            }
+            ErrorKind::TooBig => write!(f, "compiled pattern is too big: {}", self.source)?,
+        }
+        match (&self.pattern, self.span()) {
+            (Some(pattern), Some(span)) => write_carets(f, pattern, span),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.source {
+            Source::Syntax(ref e) => Some(e),
+            Source::Nfa(ref e) => Some(e),
+            Source::Dfa(ref e) => Some(e),
+            #[cfg(feature = "hybrid")]
+            Source::Lazy(ref e) => Some(e),
+        }
+    }
+}
+
+// === impl Source ===
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Syntax(e) => fmt::Display::fmt(e, f),
+            Source::Nfa(e) => fmt::Display::fmt(e, f),
+            Source::Dfa(e) => fmt::Display::fmt(e, f),
+            #[cfg(feature = "hybrid")]
+            Source::Lazy(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+fn range(span: &ast::Span) -> Range<usize> {
+    span.start.offset..span.end.offset
+}
+
+/// Errors from compiling an already parsed regex can't be attributed to a
+/// part of it, and, unless they exceeded a size limit, are caused by regex
+/// features that the automaton can't represent.
+fn unsupported(error: &impl fmt::Display) -> ErrorKind {
+    ErrorKind::Unsupported {
+        span: None,
+        message: error.to_string(),
+    }
+}
+
+/// Writes the line of `pattern` containing the start of `span`, followed by
+/// a line with carets underneath the span.
+fn write_carets(f: &mut fmt::Formatter<'_>, pattern: &str, span: &Range<usize>) -> fmt::Result {
+    let start = span.start.min(pattern.len());
+    let line_start = pattern[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = pattern[start..]
+        .find('\n')
+        .map_or(pattern.len(), |i| start + i);
+    write!(f, "\n    {}\n    ", &pattern[line_start..line_end])?;
+    // Tabs are kept, so that the carets line up however wide they are.
+    for c in pattern[line_start..start].chars() {
+        f.write_char(if c == '\t' { '\t' } else { ' ' })?;
+    }
+    let end = span.end.clamp(start, line_end);
+    for _ in 0..pattern[start..end].chars().count().max(1) {
+        f.write_char('^')?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ErrorKind, Pattern, PatternBuilder, PatternSet};
+
+    #[test]
+    fn syntax() {
+        let err = Pattern::new("a(b").unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ErrorKind::Syntax {
+                span: 1..2,
+                message: "unclosed group".to_string(),
+            }
+        );
+        assert_eq!(err.to_string(), "unclosed group\n    a(b\n     ^");
+
+        let err = Pattern::new("x{2,1}").unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Syntax { span, .. } if *span == (1..6)));
+        assert!(err.to_string().ends_with("\n    x{2,1}\n     ^^^^^"));
+    }
+
+    #[test]
+    fn syntax_multi_line() {
+        let err = PatternBuilder::new()
+            .ignore_whitespace(true)
+            .build("foo\n\tbar[z-a]\nbaz")
+            .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Syntax { span, .. } if *span == (9..12)));
+        assert!(err.to_string().ends_with("\n    \tbar[z-a]\n    \t    ^^^"));
+    }
+
+    #[test]
+    fn syntax_in_set() {
+        let err = PatternSet::new(["foo", "ba(r", "baz"]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Syntax { span, .. } if *span == (2..3)));
+        assert!(err.to_string().ends_with("\n    ba(r\n      ^"));
+    }
+
+    #[test]
+    fn unsupported() {
+        let err = Pattern::new("foo(?=bar)").unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Unsupported { span: Some(span), .. } if *span == (3..6)
+        ));
+        assert!(err.to_string().ends_with("\n    foo(?=bar)\n       ^^^"));
+
+        let err = Pattern::new(r"(a)\1").unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Unsupported { span: Some(_), .. }
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn unsupported_word_boundary() {
+        // Unicode word boundaries are valid syntax, but DFAs can't match them.
+        let err = Pattern::new(r"\bfoo\b").unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Unsupported { span: None, .. }
+        ));
+        assert!(!err.to_string().contains('\n'));
+
+        assert!(Pattern::new(r"(?-u:\b)foo(?-u:\b)").is_ok());
+    }
+
+    #[test]
+    #[cfg(not(feature = "unicode"))]
+    fn unsupported_unicode_class() {
+        let err = Pattern::new(r"a\w+").unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Unsupported { span: Some(span), .. } if *span == (1..3)
+        ));
+        assert!(err.to_string().ends_with("\n    a\\w+\n     ^^"));
+    }
+
+    #[test]
+    fn too_big() {
+        let err = PatternBuilder::new()
+            .nfa_size_limit(Some(100))
+            .build("[a-z]{100}")
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TooBig);
+        assert!(err.to_string().starts_with("compiled pattern is too big"));
+    }
+}
@@ -0,0 +1,455 @@
+#[cfg(feature = "std")]
+use crate::READ_BUF_LEN;
+use crate::{Backend, MatchStatus, Matcher, Pattern};
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{fmt, ops};
+#[cfg(feature = "std")]
+use std::io;
+
+use regex_automata::dfa::dense::DFA;
+
+/// A boolean expression over several [`Pattern`]s, which matches an input by
+/// running every pattern over it at once.
+///
+/// A `PatternExpr` is created by combining patterns with [`Pattern::and`],
+/// [`Pattern::or`], and the `!` operator. Matching an expression only formats
+/// or reads the input once, however many patterns it contains, and stops as
+/// soon as the result of the whole expression is decided (see
+/// [`Matcher::status`]).
+///
+/// For example:
+/// ```
+/// use matchers::Pattern;
+///
+/// #[derive(Debug)]
+/// struct Event {
+///     level: &'static str,
+///     message: &'static str,
+/// }
+///
+/// let foo = Pattern::new("foo.*").expect("regex is not invalid");
+/// let bar = Pattern::new("bar.*").expect("regex is not invalid");
+/// // contains(foo) AND NOT contains(bar)
+/// let expr = foo.and(!bar);
+///
+/// assert!(expr.debug_matches(&Event { level: "info", message: "foo" }));
+/// assert!(!expr.debug_matches(&Event { level: "info", message: "foo bar" }));
+/// assert!(!expr.debug_matches(&Event { level: "info", message: "baz" }));
+/// ```
+///
+/// [`Pattern`]: ../struct.Pattern.html
+/// [`Pattern::and`]: ../struct.Pattern.html#method.and
+/// [`Pattern::or`]: ../struct.Pattern.html#method.or
+/// [`Matcher::status`]: ../struct.Matcher.html#method.status
+#[derive(Debug, Clone)]
+pub struct PatternExpr<A = DFA<Vec<u32>>> {
+    patterns: Vec<Pattern<A>>,
+    root: Node,
+}
+
+/// A reference to a [`PatternExpr`] that matches a single input.
+///
+/// [`PatternExpr`]: ../struct.PatternExpr.html
+#[derive(Debug, Clone)]
+pub struct ExprMatcher<'a, A: Backend = DFA<Vec<u32>>> {
+    matchers: Vec<Matcher<&'a A>>,
+    root: &'a Node,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    /// The index of a pattern in the expression.
+    Pattern(usize),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+}
+
+// === impl Pattern ===
+
+impl<A> Pattern<A> {
+    /// Returns an expression that matches an input if both this pattern and
+    /// `other` match it.
+    ///
+    /// The patterns are moved into the expression. To use the same pattern
+    /// in several expressions without copying its automaton, convert it with
+    /// [`Pattern::into_shared`] first.
+    ///
+    /// [`Pattern::into_shared`]: #method.into_shared
+    pub fn and(self, other: impl Into<PatternExpr<A>>) -> PatternExpr<A> {
+        PatternExpr::from(self).and(other)
+    }
+
+    /// Returns an expression that matches an input if this pattern, `other`,
+    /// or both match it.
+    ///
+    /// See [`Pattern::and`] for details.
+    ///
+    /// [`Pattern::and`]: #method.and
+    pub fn or(self, other: impl Into<PatternExpr<A>>) -> PatternExpr<A> {
+        PatternExpr::from(self).or(other)
+    }
+}
+
+/// Returns an expression that matches an input if this pattern does not.
+impl<A> ops::Not for Pattern<A> {
+    type Output = PatternExpr<A>;
+
+    fn not(self) -> PatternExpr<A> {
+        !PatternExpr::from(self)
+    }
+}
+
+// === impl PatternExpr ===
+
+impl<A> PatternExpr<A> {
+    /// Returns an expression that matches an input if both this expression
+    /// and `other` match it.
+    pub fn and(self, other: impl Into<PatternExpr<A>>) -> Self {
+        self.combine(other.into(), Node::And)
+    }
+
+    /// Returns an expression that matches an input if this expression,
+    /// `other`, or both match it.
+    pub fn or(self, other: impl Into<PatternExpr<A>>) -> Self {
+        self.combine(other.into(), Node::Or)
+    }
+
+    fn combine(mut self, other: Self, op: fn(Box<Node>, Box<Node>) -> Node) -> Self {
+        let offset = self.patterns.len();
+        let mut rhs = other.root;
+        rhs.offset(offset);
+        self.patterns.extend(other.patterns);
+        self.root = op(Box::new(self.root), Box::new(rhs));
+        self
+    }
+}
+
+impl<A: Backend> PatternExpr<A> {
+    /// Obtains a matcher for this expression.
+    pub fn matcher(&self) -> ExprMatcher<'_, A> {
+        ExprMatcher {
+            matchers: self.patterns.iter().map(Pattern::matcher).collect(),
+            root: &self.root,
+        }
+    }
+
+    /// Returns `true` if this expression matches the given string.
+    #[inline]
+    pub fn matches(&self, s: &impl AsRef<str>) -> bool {
+        self.matcher().matches(s)
+    }
+
+    /// Returns `true` if this expression matches the formatted output of the
+    /// given type implementing `fmt::Debug`.
+    #[inline]
+    pub fn debug_matches(&self, d: &impl fmt::Debug) -> bool {
+        self.matcher().debug_matches(d)
+    }
+
+    /// Returns `true` if this expression matches the formatted output of the
+    /// given type implementing `fmt::Display`.
+    #[inline]
+    pub fn display_matches(&self, d: &impl fmt::Display) -> bool {
+        self.matcher().display_matches(d)
+    }
+
+    /// Returns either a `bool` indicating whether or not this expression
+    /// matches the data read from the provided `io::Read` stream, or an
+    /// `io::Error` if an error occurred reading from the stream.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn read_matches(&self, io: impl io::Read) -> io::Result<bool> {
+        self.matcher().read_matches(io)
+    }
+}
+
+impl<A> From<Pattern<A>> for PatternExpr<A> {
+    fn from(pattern: Pattern<A>) -> Self {
+        Self {
+            patterns: vec![pattern],
+            root: Node::Pattern(0),
+        }
+    }
+}
+
+/// Returns an expression that matches an input if this expression does not.
+impl<A> ops::Not for PatternExpr<A> {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self {
+            patterns: self.patterns,
+            root: Node::Not(Box::new(self.root)),
+        }
+    }
+}
+
+// === impl ExprMatcher ===
+
+impl<A: Backend> ExprMatcher<'_, A> {
+    /// Returns `true` if the input provided so far matches this expression.
+    pub fn is_matched(&self) -> bool {
+        self.root.is_matched(&self.matchers)
+    }
+
+    /// Returns whether providing more input to this `ExprMatcher` could
+    /// change whether it matches.
+    ///
+    /// An expression may be decided before all of its patterns are: for
+    /// example, `a.and(b)` is rejected as soon as `a` is. See
+    /// [`Matcher::status`] for when a single pattern is decided.
+    ///
+    /// [`Matcher::status`]: ../struct.Matcher.html#method.status
+    pub fn status(&self) -> MatchStatus {
+        self.root.status(&mut |i| self.matchers[i].status())
+    }
+
+    /// Returns `true` if this expression matches the given string.
+    pub fn matches(mut self, s: &impl AsRef<str>) -> bool {
+        self.advance_bytes(s.as_ref().as_bytes());
+        self.is_matched()
+    }
+
+    /// Returns `true` if this expression matches the formatted output of the
+    /// given type implementing `fmt::Debug`.
+    pub fn debug_matches(mut self, d: &impl fmt::Debug) -> bool {
+        use core::fmt::Write;
+        // An error means that the result was decided before `d` was fully
+        // formatted, so the rest of its output can be ignored.
+        let _ = write!(&mut self, "{:?}", d);
+        self.is_matched()
+    }
+
+    /// Returns `true` if this expression matches the formatted output of the
+    /// given type implementing `fmt::Display`.
+    pub fn display_matches(mut self, d: &impl fmt::Display) -> bool {
+        use core::fmt::Write;
+        // An error means that the result was decided before `d` was fully
+        // formatted, so the rest of its output can be ignored.
+        let _ = write!(&mut self, "{}", d);
+        self.is_matched()
+    }
+
+    /// Returns either a `bool` indicating whether or not this expression
+    /// matches the data read from the provided `io::Read` stream, or an
+    /// `io::Error` if an error occurred reading from the stream.
+    ///
+    /// Reading stops as soon as the result of the expression is decided.
+    #[cfg(feature = "std")]
+    pub fn read_matches(mut self, mut io: impl io::Read) -> io::Result<bool> {
+        let mut buf = [0u8; READ_BUF_LEN];
+        loop {
+            let n = match io.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if self.advance_bytes(&buf[..n]) {
+                break;
+            }
+        }
+        Ok(self.is_matched())
+    }
+
+    /// Advances every pattern whose result isn't decided yet over a chunk of
+    /// input, and returns `true` if the result of the expression is decided.
+    fn advance_bytes(&mut self, bytes: &[u8]) -> bool {
+        for matcher in &mut self.matchers {
+            if !matcher.is_decided() {
+                matcher.advance_bytes(bytes);
+            }
+        }
+        self.is_decided()
+    }
+
+    fn is_decided(&mut self) -> bool {
+        let matchers = &mut self.matchers;
+        let status = self.root.status(&mut |i| {
+            let matcher = &mut matchers[i];
+            if !matcher.is_decided() {
+                MatchStatus::Pending
+            } else if matcher.is_matched() {
+                MatchStatus::Matched
+            } else {
+                MatchStatus::Rejected
+            }
+        });
+        status != MatchStatus::Pending
+    }
+}
+
+/// Writing to an `ExprMatcher` returns an error once further input can no
+/// longer change whether it matches (see [`ExprMatcher::status`]), so that
+/// formatting can stop early.
+///
+/// [`ExprMatcher::status`]: struct.ExprMatcher.html#method.status
+impl<A: Backend> fmt::Write for ExprMatcher<'_, A> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.is_decided() || self.advance_bytes(s.as_bytes()) {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Backend> io::Write for ExprMatcher<'_, A> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, io::Error> {
+        if !self.is_decided() {
+            self.advance_bytes(bytes);
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+// === impl Node ===
+
+impl Node {
+    fn offset(&mut self, offset: usize) {
+        match self {
+            Node::Pattern(i) => *i += offset,
+            Node::And(lhs, rhs) | Node::Or(lhs, rhs) => {
+                lhs.offset(offset);
+                rhs.offset(offset);
+            }
+            Node::Not(node) => node.offset(offset),
+        }
+    }
+
+    fn is_matched<A: Backend>(&self, matchers: &[Matcher<&A>]) -> bool {
+        match self {
+            Node::Pattern(i) => matchers[*i].is_matched(),
+            Node::And(lhs, rhs) => lhs.is_matched(matchers) && rhs.is_matched(matchers),
+            Node::Or(lhs, rhs) => lhs.is_matched(matchers) || rhs.is_matched(matchers),
+            Node::Not(node) => !node.is_matched(matchers),
+        }
+    }
+
+    /// Evaluates the expression with three-valued logic, given the status of
+    /// each pattern.
+    fn status(&self, pattern: &mut impl FnMut(usize) -> MatchStatus) -> MatchStatus {
+        use MatchStatus::*;
+        match self {
+            Node::Pattern(i) => pattern(*i),
+            Node::And(lhs, rhs) => match lhs.status(pattern) {
+                Rejected => Rejected,
+                Matched => rhs.status(pattern),
+                Pending => match rhs.status(pattern) {
+                    Rejected => Rejected,
+                    _ => Pending,
+                },
+            },
+            Node::Or(lhs, rhs) => match lhs.status(pattern) {
+                Matched => Matched,
+                Rejected => rhs.status(pattern),
+                Pending => match rhs.status(pattern) {
+                    Matched => Matched,
+                    _ => Pending,
+                },
+            },
+            Node::Not(node) => match node.status(pattern) {
+                Matched => Rejected,
+                Rejected => Matched,
+                Pending => Pending,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PatternBuilder;
+    use core::fmt::Write;
+
+    fn pattern(regex: &str) -> Pattern {
+        Pattern::new(regex).unwrap()
+    }
+
+    #[test]
+    fn and_or_not() {
+        let expr = pattern("foo.*").and(!pattern("bar.*"));
+        assert!(expr.matches(&"a foo"));
+        assert!(!expr.matches(&"a foo bar"));
+        assert!(!expr.matches(&"a baz"));
+
+        let expr = pattern("foo.*").or(pattern("bar.*"));
+        assert!(expr.matches(&"a foo"));
+        assert!(expr.matches(&"a bar"));
+        assert!(expr.matches(&"bar foo"));
+        assert!(!expr.matches(&"a baz"));
+
+        let expr = !pattern("foo.*").or(pattern("bar.*"));
+        assert!(!expr.matches(&"a foo"));
+        assert!(expr.matches(&"a baz"));
+    }
+
+    #[test]
+    fn nested_exprs() {
+        // (a AND b) OR (NOT c AND d)
+        let lhs = pattern("a.*").and(pattern(".*b.*"));
+        let rhs = (!pattern("c.*")).and(pattern(".*d.*"));
+        let expr = lhs.or(rhs);
+        assert!(expr.matches(&"ab"));
+        assert!(expr.matches(&"d"));
+        assert!(!expr.matches(&"cd"));
+        assert!(expr.matches(&"abcd"));
+        assert!(!expr.matches(&"a"));
+    }
+
+    #[test]
+    fn reads_and_writes() {
+        let expr = pattern("foo.*").and(!pattern("bar.*"));
+        assert!(expr.read_matches(&b"xfoo"[..]).unwrap());
+        assert!(!expr.read_matches(&b"xfoobar"[..]).unwrap());
+
+        let mut matcher = expr.matcher();
+        io::Write::write_all(&mut matcher, b"fo").unwrap();
+        assert!(!matcher.is_matched());
+        io::Write::write_all(&mut matcher, b"o!").unwrap();
+        assert!(matcher.is_matched());
+    }
+
+    #[test]
+    fn short_circuits() {
+        let new = |regex| {
+            PatternBuilder::new()
+                .anchored(true)
+                .utf8(false)
+                .build(regex)
+                .unwrap()
+        };
+        let expr = new("(?s-u:error.*)").and(!new("(?s-u:.*timeout.*)"));
+        let mut matcher = expr.matcher();
+        // The first pattern rejects the input, so the whole expression does.
+        assert!(write!(matcher, "warn").is_err());
+        assert_eq!(matcher.status(), MatchStatus::Rejected);
+
+        let expr = new("(?s-u:error.*)").or(new("(?s-u:.*timeout.*)"));
+        let mut matcher = expr.matcher();
+        write!(matcher, "err").unwrap();
+        assert_eq!(matcher.status(), MatchStatus::Pending);
+        // The first pattern matches whatever follows, so the whole
+        // expression does.
+        assert!(write!(matcher, "or: oh no").is_err());
+        assert_eq!(matcher.status(), MatchStatus::Matched);
+        assert!(matcher.is_matched());
+    }
+
+    #[test]
+    fn shared_patterns() {
+        let foo = pattern("foo.*").into_shared();
+        let bar = pattern("bar.*").into_shared();
+        let both = foo.clone().and(bar.clone());
+        let either = foo.or(bar);
+        assert!(!both.matches(&"foo"));
+        assert!(either.matches(&"foo"));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::search::{Piece, Search};
+use crate::{Pattern, READ_BUF_LEN};
+use std::collections::VecDeque;
+use std::io;
+
+use regex_automata::dfa::dense::DFA;
+use regex_automata::dfa::Automaton;
+
+/// An iterator over the byte offsets of every match of a [`Pattern`] in the
+/// data read from an `io::Read` stream.
+///
+/// This is returned by [`Pattern::find_iter_read`].
+///
+/// [`Pattern`]: ../struct.Pattern.html
+/// [`Pattern::find_iter_read`]: ../struct.Pattern.html#method.find_iter_read
+#[derive(Debug)]
+pub struct FindIter<'a, R, A = DFA<Vec<u32>>> {
+    search: Search<&'a A>,
+    reader: R,
+    buf: Vec<u8>,
+    found: VecDeque<(u64, u64)>,
+    eof: bool,
+}
+
+// === impl Pattern ===
+
+impl<A: Automaton> Pattern<A> {
+    /// Returns an iterator over the start and end byte offsets of every
+    /// non-overlapping match of this pattern in the data read from the
+    /// provided `io::Read` stream.
+    ///
+    /// Matches are found in the same way as by the [`regex`] crate's
+    /// `find_iter`, except that empty matches are skipped. The stream is read
+    /// in chunks, and only the bytes that may be part of a match that hasn't
+    /// ended yet are buffered, up to the limit set by
+    /// [`FindIter::max_lookahead`].
+    ///
+    /// If reading from the stream fails, the iterator yields the error, and
+    /// will try to read from the stream again if it is advanced further.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let pattern = Pattern::new("[0-9]+").expect("regex is not invalid");
+    /// let input = "1 fish, 22 fish, red fish, 333 fish";
+    /// let matches = pattern
+    ///     .find_iter_read(input.as_bytes())
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .expect("reading from a slice does not fail");
+    /// assert_eq!(matches, vec![(0, 1), (8, 10), (27, 30)]);
+    /// ```
+    ///
+    /// [`regex`]: https://crates.io/crates/regex
+    /// [`FindIter::max_lookahead`]: struct.FindIter.html#method.max_lookahead
+    pub fn find_iter_read<R: io::Read>(&self, reader: R) -> FindIter<'_, R, A> {
+        FindIter {
+            search: Search::new(&self.automaton, self.anchored),
+            reader,
+            buf: vec![0; READ_BUF_LEN],
+            found: VecDeque::new(),
+            eof: false,
+        }
+    }
+}
+
+// === impl FindIter ===
+
+impl<R, A: Automaton> FindIter<'_, R, A> {
+    /// Sets the maximum number of bytes that will be buffered while waiting
+    /// to find out whether they are part of a match.
+    ///
+    /// Matches longer than this are cut short. By default, this is 64 KiB.
+    pub fn max_lookahead(&mut self, limit: usize) -> &mut Self {
+        self.search.set_max_lookahead(limit);
+        self
+    }
+
+    fn drain(&mut self) {
+        let found = &mut self.found;
+        let _ = self.search.drain(|piece| {
+            if let Piece::Match { start, end } = piece {
+                found.push_back((start, end));
+            }
+            Ok::<_, ()>(())
+        });
+    }
+}
+
+impl<R: io::Read, A: Automaton> Iterator for FindIter<'_, R, A> {
+    type Item = io::Result<(u64, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(found) = self.found.pop_front() {
+                return Some(Ok(found));
+            }
+            if self.eof {
+                return None;
+            }
+            match self.reader.read(&mut self.buf) {
+                Ok(0) => {
+                    self.search.finish();
+                    self.eof = true;
+                }
+                Ok(n) => self.search.push(&self.buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            }
+            self.drain();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A reader that returns a single byte at a time.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&byte, rest)) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    fn find(pattern: &Pattern, reader: impl io::Read) -> Vec<(u64, u64)> {
+        pattern
+            .find_iter_read(reader)
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_matches() {
+        let pat = Pattern::new("a+b").unwrap();
+        assert_eq!(
+            find(&pat, &b"xaab ab b aaab"[..]),
+            vec![(1, 4), (5, 7), (10, 14)]
+        );
+        assert_eq!(find(&pat, &b"nothing here"[..]), vec![]);
+        assert_eq!(find(&pat, &b""[..]), vec![]);
+    }
+
+    #[test]
+    fn finds_matches_across_reads() {
+        let pat = Pattern::new("a+b").unwrap();
+        let input = b"xaab ab b aaab";
+        assert_eq!(find(&pat, Trickle(input)), find(&pat, &input[..]));
+    }
+
+    #[test]
+    fn finds_matches_across_buffers() {
+        let pat = Pattern::new("needle").unwrap();
+        let mut input = vec![b'.'; READ_BUF_LEN - 3];
+        input.extend_from_slice(b"needle");
+        input.extend(vec![b'.'; READ_BUF_LEN]);
+        input.extend_from_slice(b"needle");
+        let end = input.len() as u64;
+        assert_eq!(
+            find(&pat, &input[..]),
+            vec![
+                (READ_BUF_LEN as u64 - 3, READ_BUF_LEN as u64 + 3),
+                (end - 6, end)
+            ]
+        );
+    }
+
+    #[test]
+    fn anchored() {
+        let pat = Pattern::new_anchored("a+b").unwrap();
+        assert_eq!(find(&pat, &b"aab ab"[..]), vec![(0, 3)]);
+        assert_eq!(find(&pat, &b"xaab"[..]), vec![]);
+    }
+
+    #[test]
+    fn yields_read_errors() {
+        struct Failing(bool);
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                if self.0 {
+                    return Ok(0);
+                }
+                self.0 = true;
+                Err(io::Error::other("oh no"))
+            }
+        }
+
+        let pat = Pattern::new("a+b").unwrap();
+        let mut iter = pat.find_iter_read(Failing(false));
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{Backend, Matcher, Pattern, READ_BUF_LEN};
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use ::futures_io::{AsyncRead, AsyncWrite};
+
+// === impl Pattern ===
+
+impl<A: Backend> Pattern<A> {
+    /// Returns either a `bool` indicating whether or not this pattern matches the
+    /// data read from the provided `futures_io::AsyncRead` stream, or an
+    /// `io::Error` if an error occurred reading from the stream.
+    ///
+    /// This requires the `futures-io` feature. It is the equivalent of
+    /// `async_read_matches` (with the `tokio` feature) for the `AsyncRead`
+    /// trait used by `futures`, `async-std` and `smol`.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let pattern = Pattern::new("hello world").expect("regex is not invalid");
+    /// let matched = futures_lite::future::block_on(pattern.futures_read_matches(&b"hello world"[..]))
+    ///     .expect("reading from a slice does not fail");
+    /// assert!(matched);
+    /// ```
+    #[inline]
+    pub async fn futures_read_matches(&self, io: impl AsyncRead) -> io::Result<bool> {
+        self.matcher().futures_read_matches(io).await
+    }
+}
+
+// === impl Matcher ===
+
+impl<A: Backend> Matcher<A> {
+    /// Returns either a `bool` indicating whether or not this pattern matches the
+    /// data read from the provided `futures_io::AsyncRead` stream, or an
+    /// `io::Error` if an error occurred reading from the stream.
+    ///
+    /// Like [`read_matches`], the stream is read in chunks into a fixed-size
+    /// buffer, and reading stops as soon as the input can no longer match.
+    ///
+    /// This requires the `futures-io` feature.
+    ///
+    /// [`read_matches`]: #method.read_matches
+    pub async fn futures_read_matches(mut self, io: impl AsyncRead) -> io::Result<bool> {
+        let mut io = std::pin::pin!(io);
+        let mut buf = [0u8; READ_BUF_LEN];
+        loop {
+            let n = match poll_fn(|cx| io.as_mut().poll_read(cx, &mut buf)).await {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.advance_bytes(&buf[..n]);
+            if self.automaton.is_dead_state(self.state) {
+                return Ok(false);
+            }
+        }
+        Ok(self.is_matched())
+    }
+}
+
+/// Matching never blocks, so writes to a `Matcher` always complete
+/// immediately, consuming input in the same way as its `io::Write`
+/// implementation.
+///
+/// This requires the `futures-io` feature.
+impl<A: Backend> AsyncWrite for Matcher<A> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        bytes: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(self.get_mut().advance_bytes(bytes)))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures_lite::future::block_on;
+    use futures_lite::io::AsyncWriteExt;
+
+    /// A reader that returns one byte at a time, and is only ready on every
+    /// other poll.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        ready: bool,
+    }
+
+    impl AsyncRead for Trickle<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+            match self.bytes.split_first() {
+                Some((&byte, rest)) => {
+                    buf[0] = byte;
+                    self.bytes = rest;
+                    Poll::Ready(Ok(1))
+                }
+                None => Poll::Ready(Ok(0)),
+            }
+        }
+    }
+
+    fn trickle(bytes: &[u8]) -> Trickle<'_> {
+        Trickle {
+            bytes,
+            ready: false,
+        }
+    }
+
+    #[test]
+    fn read_matches() {
+        let pattern = Pattern::new("hello world").unwrap();
+        assert!(block_on(pattern.futures_read_matches(trickle(b"why, hello world"))).unwrap());
+        assert!(!block_on(pattern.futures_read_matches(trickle(b"goodbye world"))).unwrap());
+    }
+
+    #[test]
+    fn read_stops_at_dead_state() {
+        let pattern = Pattern::new_anchored("a+b").unwrap();
+        let mut reader = trickle(b"qqqqqq");
+        assert!(!block_on(pattern.matcher().futures_read_matches(&mut reader)).unwrap());
+        assert_eq!(reader.bytes, b"qqqqq");
+    }
+
+    #[test]
+    fn async_write() {
+        let pattern = Pattern::new("a+b").unwrap();
+        let mut matcher = pattern.matcher();
+        block_on(async {
+            matcher.write_all(b"ffaa").await.unwrap();
+            assert!(!matcher.is_matched());
+            matcher.write_all(b"ab").await.unwrap();
+            matcher.close().await.unwrap();
+        });
+        assert!(matcher.is_matched());
+    }
+
+    #[test]
+    fn copy_into_matcher() {
+        let pattern = Pattern::new("[0-9]+ bytes").unwrap();
+        let mut matcher = pattern.matcher();
+        let copied = block_on(futures_lite::io::copy(
+            trickle(b"sent 1024 bytes"),
+            &mut matcher,
+        ));
+        assert_eq!(copied.unwrap(), 15);
+        assert!(matcher.is_matched());
+    }
+}
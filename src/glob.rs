@@ -0,0 +1,430 @@
+use crate::{Pattern, PatternBuilder};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+/// A builder for configuring how a shell-style glob is compiled into a
+/// [`Pattern`].
+///
+/// Globs are translated into a regex, and compiled into the same kind of DFA
+/// as any other pattern, so they can match streaming output in the same
+/// way. A glob always matches the entire input. The following syntax is
+/// supported:
+///
+/// - `?` matches any single character.
+/// - `*` matches any sequence of characters, including an empty one.
+/// - `**` matches any sequence of characters, including the
+///   [separator](#method.separator), if one is set. Otherwise, it is the
+///   same as `*`.
+/// - `[abc]` and `[a-z]` match any character in the class, and `[!abc]` or
+///   `[^abc]` match any character that isn't. A `]` may be included in the
+///   class by placing it first, and a `-` by placing it first or last.
+/// - `{a,b}` matches any of the comma-separated alternatives, which may
+///   themselves contain any glob syntax, including other alternations.
+/// - `\` escapes the character that follows it, so that it is matched
+///   literally.
+///
+/// Every other character matches itself.
+///
+/// For example:
+/// ```
+/// use matchers::GlobBuilder;
+///
+/// let pattern = GlobBuilder::new()
+///     .separator('.')
+///     .build("http.*.latency")
+///     .expect("glob is not invalid");
+///
+/// assert!(pattern.matches(&"http.get.latency"));
+/// assert!(!pattern.matches(&"http.get.v2.latency"));
+/// ```
+///
+/// [`Pattern`]: ../struct.Pattern.html
+#[derive(Debug, Clone, Default)]
+pub struct GlobBuilder {
+    separator: Option<char>,
+    case_insensitive: bool,
+}
+
+/// An error that occurred while compiling a glob into a [`Pattern`].
+///
+/// [`Pattern`]: ../struct.Pattern.html
+#[derive(Debug)]
+pub struct GlobError {
+    kind: ErrorKind,
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    UnclosedClass { pos: usize },
+    UnclosedAlternation { pos: usize },
+    UnopenedAlternation { pos: usize },
+    DanglingEscape { pos: usize },
+    Build(crate::Error),
+}
+
+// === impl Pattern ===
+
+impl Pattern {
+    /// Returns a new `Pattern` that matches the given shell-style glob, or
+    /// an error if the glob was invalid.
+    ///
+    /// The glob must match the entire input. See [`GlobBuilder`] for the
+    /// supported syntax, and for options such as separator-aware wildcards.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let pattern = Pattern::from_glob("db-??-{primary,replica}").expect("glob is not invalid");
+    ///
+    /// assert!(pattern.display_matches(&"db-01-primary"));
+    /// assert!(pattern.display_matches(&"db-02-replica"));
+    /// assert!(!pattern.display_matches(&"db-003-primary"));
+    /// ```
+    ///
+    /// [`GlobBuilder`]: ../struct.GlobBuilder.html
+    pub fn from_glob(glob: &str) -> Result<Self, GlobError> {
+        GlobBuilder::new().build(glob)
+    }
+}
+
+// === impl GlobBuilder ===
+
+impl GlobBuilder {
+    /// Returns a new `GlobBuilder` with the default configuration.
+    ///
+    /// By default, globs have no separator, and are case-sensitive.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compiles the given glob into a [`Pattern`] using this builder's
+    /// configuration, or returns an error if the glob was invalid.
+    ///
+    /// [`Pattern`]: ../struct.Pattern.html
+    pub fn build(&self, glob: &str) -> Result<Pattern, GlobError> {
+        let regex = self.to_regex(glob)?;
+        PatternBuilder::new()
+            .anchored(true)
+            .build(&regex)
+            .map_err(|e| ErrorKind::Build(e).into())
+    }
+
+    /// Sets a separator character that `*` and `?` do not match.
+    ///
+    /// Only `**` matches across separators, so with a separator of `.`, the
+    /// glob `http.*` matches `http.get` but not `http.get.latency`, while
+    /// `http.**` matches both. By default, there is no separator.
+    pub fn separator(&mut self, separator: char) -> &mut Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// Enables or disables case-insensitive matching.
+    ///
+    /// Only ASCII letters are matched case-insensitively. By default, this is
+    /// disabled.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.case_insensitive = yes;
+        self
+    }
+
+    /// Translates a glob into an equivalent regex.
+    fn to_regex(&self, glob: &str) -> Result<String, GlobError> {
+        let mut regex = String::from("(?s)");
+        // The offsets of the unclosed `{`s.
+        let mut alternations = Vec::new();
+        let mut chars = glob.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '?' => self.push_any(&mut regex),
+                '*' => {
+                    let mut double = false;
+                    while chars.next_if(|&(_, c)| c == '*').is_some() {
+                        double = true;
+                    }
+                    if double {
+                        regex.push_str(".*");
+                    } else {
+                        self.push_any(&mut regex);
+                        regex.push('*');
+                    }
+                }
+                '[' => self.push_class(&mut regex, &mut chars, pos)?,
+                '{' => {
+                    alternations.push(pos);
+                    regex.push_str("(?:");
+                }
+                ',' if !alternations.is_empty() => regex.push('|'),
+                '}' => {
+                    alternations
+                        .pop()
+                        .ok_or(ErrorKind::UnopenedAlternation { pos })?;
+                    regex.push(')');
+                }
+                '\\' => {
+                    let (_, c) = chars.next().ok_or(ErrorKind::DanglingEscape { pos })?;
+                    self.push_literal(&mut regex, c);
+                }
+                c => self.push_literal(&mut regex, c),
+            }
+        }
+        if let Some(&pos) = alternations.first() {
+            return Err(ErrorKind::UnclosedAlternation { pos }.into());
+        }
+        // Require the glob to match all the way to the end of the input, so
+        // that an earlier alternative that matches a prefix can't prevent a
+        // later one from matching the rest.
+        regex.push_str(r"\z");
+        Ok(regex)
+    }
+
+    /// Pushes a regex that matches any single character except the
+    /// separator.
+    fn push_any(&self, regex: &mut String) {
+        match self.separator {
+            Some(separator) => {
+                regex.push_str("[^");
+                push_escaped(regex, separator);
+                regex.push(']');
+            }
+            None => regex.push('.'),
+        }
+    }
+
+    fn push_literal(&self, regex: &mut String, c: char) {
+        if self.case_insensitive && c.is_ascii_alphabetic() {
+            regex.push('[');
+            regex.push(c.to_ascii_lowercase());
+            regex.push(c.to_ascii_uppercase());
+            regex.push(']');
+        } else {
+            push_escaped(regex, c);
+        }
+    }
+
+    /// Pushes the regex for a character class, given the glob's characters
+    /// following the opening `[` at `start`.
+    fn push_class(
+        &self,
+        regex: &mut String,
+        chars: &mut core::iter::Peekable<core::str::CharIndices<'_>>,
+        start: usize,
+    ) -> Result<(), GlobError> {
+        let unclosed = || GlobError::from(ErrorKind::UnclosedClass { pos: start });
+        regex.push('[');
+        if chars.next_if(|&(_, c)| c == '!' || c == '^').is_some() {
+            regex.push('^');
+        }
+        let mut first = true;
+        loop {
+            let (_, lo) = chars.next().ok_or_else(unclosed)?;
+            if lo == ']' && !first {
+                break;
+            }
+            first = false;
+            let mut hi = lo;
+            // A `-` is a range unless it's the last character in the class.
+            if let Some(&(_, '-')) = chars.peek() {
+                let mut lookahead = chars.clone();
+                lookahead.next();
+                match lookahead.next() {
+                    Some((_, ']')) => {}
+                    Some((_, c)) => {
+                        hi = c;
+                        *chars = lookahead;
+                    }
+                    None => return Err(unclosed()),
+                }
+            }
+            self.push_range(regex, lo, hi);
+        }
+        regex.push(']');
+        Ok(())
+    }
+
+    fn push_range(&self, regex: &mut String, lo: char, hi: char) {
+        push_escaped(regex, lo);
+        if hi != lo {
+            regex.push('-');
+            push_escaped(regex, hi);
+        }
+        if !self.case_insensitive {
+            return;
+        }
+        // Add the other case of any ASCII letters in the range.
+        for (letters, other) in [('a'..='z', b'A'), ('A'..='Z', b'a')] {
+            let lo = lo.max(*letters.start());
+            let hi = hi.min(*letters.end());
+            if lo <= hi {
+                let shift = |c: char| (other + (c as u8 - *letters.start() as u8)) as char;
+                regex.push(shift(lo));
+                regex.push('-');
+                regex.push(shift(hi));
+            }
+        }
+    }
+}
+
+/// Pushes a character that is matched literally, both inside and outside of
+/// a character class.
+fn push_escaped(regex: &mut String, c: char) {
+    if c.is_ascii_punctuation() {
+        regex.push('\\');
+    }
+    regex.push(c);
+}
+
+// === impl GlobError ===
+
+impl From<ErrorKind> for GlobError {
+    fn from(kind: ErrorKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::UnclosedClass { pos } => {
+                write!(f, "unclosed character class at offset {}", pos)
+            }
+            ErrorKind::UnclosedAlternation { pos } => {
+                write!(f, "unclosed alternation at offset {}", pos)
+            }
+            ErrorKind::UnopenedAlternation { pos } => {
+                write!(f, "unopened alternation at offset {}", pos)
+            }
+            ErrorKind::DanglingEscape { pos } => {
+                write!(f, "dangling escape at offset {}", pos)
+            }
+            ErrorKind::Build(ref e) => write!(f, "failed to compile glob: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for GlobError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            ErrorKind::Build(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn glob(glob: &str) -> Pattern {
+        Pattern::from_glob(glob).unwrap()
+    }
+
+    #[test]
+    fn wildcards() {
+        let pat = glob("db-??-primary");
+        assert!(pat.matches(&"db-01-primary"));
+        assert!(!pat.matches(&"db-1-primary"));
+        assert!(!pat.matches(&"xdb-01-primary"));
+        assert!(!pat.matches(&"db-01-primary2"));
+
+        let pat = glob("http.*.latency");
+        assert!(pat.matches(&"http.get.latency"));
+        assert!(pat.matches(&"http..latency"));
+        assert!(pat.matches(&"http.get.v2.latency"));
+        assert!(!pat.matches(&"http.get.latency.p99"));
+        assert!(glob("*").matches(&"anything\nat all"));
+    }
+
+    #[test]
+    fn separator() {
+        let pat = GlobBuilder::new()
+            .separator('.')
+            .build("http.*.latency")
+            .unwrap();
+        assert!(pat.matches(&"http.get.latency"));
+        assert!(!pat.matches(&"http.get.v2.latency"));
+
+        let pat = GlobBuilder::new()
+            .separator('.')
+            .build("http.**.latency")
+            .unwrap();
+        assert!(pat.matches(&"http.get.v2.latency"));
+
+        let pat = GlobBuilder::new().separator('/').build("a/?").unwrap();
+        assert!(pat.matches(&"a/b"));
+        assert!(!pat.matches(&"a//"));
+    }
+
+    #[test]
+    fn classes() {
+        let pat = glob("[a-c]x[!0-9][]-]");
+        assert!(pat.matches(&"axy]"));
+        assert!(pat.matches(&"cx.-"));
+        assert!(!pat.matches(&"dxy]"));
+        assert!(!pat.matches(&"ax1]"));
+
+        let pat = glob("[*?{\\^]");
+        for s in &["*", "?", "{", "\\", "^"] {
+            assert!(pat.matches(s), "{:?}", s);
+        }
+        assert!(!pat.matches(&"a"));
+    }
+
+    #[test]
+    fn alternations() {
+        let pat = glob("{b,bc}");
+        assert!(pat.matches(&"b"));
+        assert!(pat.matches(&"bc"));
+        assert!(!pat.matches(&"bcd"));
+
+        let pat = glob("{a,b{c,d},}.log");
+        for s in &["a.log", "bc.log", "bd.log", ".log"] {
+            assert!(pat.matches(s), "{:?}", s);
+        }
+        assert!(!pat.matches(&"b.log"));
+        assert!(glob("a,b").matches(&"a,b"));
+    }
+
+    #[test]
+    fn escapes_and_literals() {
+        let pat = glob(r"\*.(txt)+");
+        assert!(pat.matches(&"*.(txt)+"));
+        assert!(!pat.matches(&"a.(txt)+"));
+        assert!(!pat.matches(&"*.txt"));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let pat = GlobBuilder::new()
+            .case_insensitive(true)
+            .build("Error-[a-c]*")
+            .unwrap();
+        assert!(pat.matches(&"ERROR-B"));
+        assert!(pat.matches(&"error-a1"));
+        assert!(!pat.matches(&"error-d"));
+
+        let pat = GlobBuilder::new()
+            .case_insensitive(true)
+            .build("[!x-z]")
+            .unwrap();
+        assert!(pat.matches(&"a"));
+        assert!(pat.matches(&"A"));
+        assert!(!pat.matches(&"y"));
+        assert!(!pat.matches(&"Y"));
+    }
+
+    #[test]
+    fn errors() {
+        let err = |glob| Pattern::from_glob(glob).unwrap_err().to_string();
+        assert_eq!(err("ab[cd"), "unclosed character class at offset 2");
+        assert_eq!(err("a[]"), "unclosed character class at offset 1");
+        assert_eq!(err("{a,{b}"), "unclosed alternation at offset 0");
+        assert_eq!(err("a}"), "unopened alternation at offset 1");
+        assert_eq!(err("a\\"), "dangling escape at offset 1");
+        assert!(Pattern::from_glob("[z-a]").is_err());
+    }
+}
@@ -15,24 +15,101 @@
 //! `io::Write` for regex patterns. This may be used to test whether streaming
 //! output matches a pattern without buffering that output.
 //!
-//! Users who need to extract substrings based on a pattern or who already have
-//! buffered data should probably use the [`regex`] crate instead.
+//! Small capture groups, such as an ID field in `fmt::Debug` output, can be
+//! extracted with [`Pattern::debug_captures`], which buffers the output up to
+//! a fixed limit. Users who need to extract larger substrings based on a
+//! pattern or who already have buffered data should probably use the
+//! [`regex`] crate instead.
 //!
 //! ## Syntax
 //!
 //! This crate uses the same [regex syntax][syntax] of the `regex-automata` crate.
 //!
+//! ## Crate Features
+//!
+//! - `std` (enabled by default): Enables matching `io::Read` streams, and
+//!   the `io::Write` implementations for matchers. Without it, this crate is
+//!   `#![no_std]`, and only requires `alloc`; patterns can still match the
+//!   output of `fmt::Debug` and `fmt::Display`, or anything else written to a
+//!   `fmt::Write`.
+//! - `unicode`: Enables Unicode support in patterns, such as Unicode classes
+//!   like `\w` and case-insensitive matching of non-ASCII characters.
+//! - `hybrid`: Enables patterns backed by lazy DFAs (see
+//!   [`Pattern::new_lazy`]).
+//! - `tokio` and `futures-io`: Enable matching asynchronous streams.
+//!
+//! [`Pattern::debug_captures`]: struct.Pattern.html#method.debug_captures
+//! [`Pattern::new_lazy`]: struct.Pattern.html#method.new_lazy
 //! [`regex`]: https://crates.io/crates/regex
 //! [`regex-automata`]: https://crates.io/crates/regex-automata
 //! [syntax]: https://docs.rs/regex-automata/0.4.3/regex_automata/#syntax
 
-use std::{fmt, io, str::FromStr};
+// `Error` holds the underlying errors from `regex-automata` and
+// `regex-syntax`, so its size is not ours to change.
+#![allow(clippy::result_large_err)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use self::captures::CaptureSource;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::{fmt, str::FromStr};
+#[cfg(feature = "std")]
+use std::io;
 
-pub use regex_automata::dfa::dense::BuildError;
+pub mod build;
+
+mod backend;
+mod builder;
+mod captures;
+mod count;
+mod error;
+mod expr;
+#[cfg(feature = "std")]
+mod find;
+#[cfg(feature = "futures-io")]
+mod futures_io;
+mod glob;
+mod lines;
+#[cfg(feature = "std")]
+mod redact;
+mod search;
+mod serialize;
+mod set;
+#[cfg(feature = "tokio")]
+mod tokio_io;
+
+pub use self::backend::Backend;
+pub use self::builder::PatternBuilder;
+pub use self::captures::{Captures, CapturesError};
+pub use self::count::CountingMatcher;
+pub use self::error::{Error, ErrorKind};
+pub use self::expr::{ExprMatcher, PatternExpr};
+#[cfg(feature = "std")]
+pub use self::find::FindIter;
+pub use self::glob::{GlobBuilder, GlobError};
+pub use self::lines::LineMatcher;
+#[cfg(feature = "std")]
+pub use self::lines::MatchingLines;
+#[cfg(feature = "std")]
+pub use self::redact::RedactingWriter;
+pub use self::serialize::DeserializeError;
+pub use self::set::{PatternSet, SetMatcher, SetMatches};
 use regex_automata::dfa::dense::DFA;
-use regex_automata::dfa::Automaton;
-use regex_automata::util::primitives::StateID;
 use regex_automata::Anchored;
+pub use regex_automata::PatternID;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use regex_automata::util::wire::AlignAs;
+}
+
+/// The size of the buffer used when matching an `io::Read` stream.
+#[cfg(feature = "std")]
+pub(crate) const READ_BUF_LEN: usize = 8 * 1024;
 
 /// A compiled match pattern that can match multipe inputs, or return a
 /// [`Matcher`] that matches a single input.
@@ -42,17 +119,75 @@ use regex_automata::Anchored;
 pub struct Pattern<A = DFA<Vec<u32>>> {
     automaton: A,
     anchored: Anchored,
+    /// The regex this pattern was compiled from, if it is known, which is
+    /// needed to extract capture groups.
+    captures: Option<Arc<CaptureSource>>,
 }
 
 /// A reference to a [`Pattern`] that matches a single input.
 ///
 /// [`Pattern`]: ../struct.Pattern.html
 #[derive(Debug, Clone)]
-pub struct Matcher<A = DFA<Vec<u32>>> {
+pub struct Matcher<A: Backend = DFA<Vec<u32>>> {
     automaton: A,
-    state: StateID,
+    cache: A::Cache,
+    anchored: Anchored,
+    /// The start state, cached so that the matcher can be reset cheaply.
+    start: A::State,
+    state: A::State,
+    /// Identifies the matcher a snapshot was taken from, so that its state
+    /// can't be restored into a matcher for a different automaton.
+    id: usize,
+    /// The last state checked for whether every continuation of the input
+    /// matches, and the result of that check.
+    universal: Option<(A::State, bool)>,
+    /// The number of bytes of input that have been provided so far.
+    pos: u64,
+    /// The offset at which the automaton first entered a match state.
+    match_end: Option<u64>,
+}
+
+/// Whether a [`Matcher`]'s result could still be changed by further input.
+///
+/// This is returned by [`Matcher::status`].
+///
+/// [`Matcher`]: ../struct.Matcher.html
+/// [`Matcher::status`]: ../struct.Matcher.html#method.status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchStatus {
+    /// The input matches the pattern, and will continue to match regardless
+    /// of any further input.
+    Matched,
+    /// The input does not match the pattern, and no further input can cause
+    /// it to match.
+    Rejected,
+    /// Whether or not the input matches depends on input that has not been
+    /// provided yet.
+    Pending,
+}
+
+/// A checkpoint of a [`Matcher`]'s progress through its input.
+///
+/// A snapshot is returned by [`Matcher::state_snapshot`], and may be passed to
+/// [`Matcher::restore`] to rewind that matcher (or a clone of it) to the
+/// point where the snapshot was taken.
+///
+/// [`Matcher`]: ../struct.Matcher.html
+/// [`Matcher::state_snapshot`]: ../struct.Matcher.html#method.state_snapshot
+/// [`Matcher::restore`]: ../struct.Matcher.html#method.restore
+#[derive(Debug, Clone)]
+pub struct MatcherSnapshot<A: Backend = DFA<Vec<u32>>> {
+    cache: A::Cache,
+    state: A::State,
+    pos: u64,
+    match_end: Option<u64>,
+    id: usize,
 }
 
+// Matchers are never pinned in place, so they may be used as `AsyncWrite`s
+// regardless of whether the automaton's state types are `Unpin`.
+impl<A: Backend> Unpin for Matcher<A> {}
+
 // === impl Pattern ===
 
 impl Pattern {
@@ -81,12 +216,8 @@ impl Pattern {
     /// // sequence when it's followed by non-matching characters:
     /// assert!(pattern.display_matches(&"hello world! aaaaab"));
     /// ```
-    pub fn new(pattern: &str) -> Result<Self, BuildError> {
-        let automaton = DFA::new(pattern)?;
-        Ok(Pattern {
-            automaton,
-            anchored: Anchored::No,
-        })
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        PatternBuilder::new().build(pattern)
     }
 
     /// Returns a new `Pattern` anchored at the beginning of the input stream,
@@ -118,34 +249,179 @@ impl Pattern {
     ///     .expect("regex is not invalid");
     /// assert!(pattern2.display_matches(&"hello world! aaaaab"));
     /// ```
-    pub fn new_anchored(pattern: &str) -> Result<Self, BuildError> {
-        let automaton = DFA::new(pattern)?;
-        Ok(Pattern {
-            automaton,
-            anchored: Anchored::Yes,
-        })
+    pub fn new_anchored(pattern: &str) -> Result<Self, Error> {
+        PatternBuilder::new().anchored(true).build(pattern)
+    }
+
+    /// Returns a new `Pattern` that matches any input containing at least one
+    /// of the given literal strings, or an error if the pattern was too
+    /// big.
+    ///
+    /// The literals are matched exactly, so they do not need to be escaped,
+    /// and may be arbitrary bytes. They are compiled into a single DFA in the
+    /// same way as [Aho-Corasick], so checking for a large number of literals
+    /// is no slower than checking for one. Case-insensitive matching of ASCII
+    /// letters may be enabled with [`PatternBuilder::build_literals`].
+    ///
+    /// Since an input matches if a literal occurs *anywhere* in it, the
+    /// pattern's matches continue to the end of the input, which makes it
+    /// suited to testing inputs, rather than finding the literals within
+    /// them with [`Pattern::find_iter_read`]. Once a literal has been found,
+    /// the pattern's [status] is decided, so formatting stops early.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let blocked = Pattern::from_literals(["BadBot/", "EvilCrawler", "scan.example"])
+    ///     .expect("literals are not too big");
+    ///
+    /// assert!(blocked.display_matches(&"Mozilla/5.0 (compatible; BadBot/1.0)"));
+    /// assert!(!blocked.display_matches(&"Mozilla/5.0 (X11; Linux x86_64)"));
+    /// ```
+    ///
+    /// [Aho-Corasick]: https://en.wikipedia.org/wiki/Aho%E2%80%93Corasick_algorithm
+    /// [`PatternBuilder::build_literals`]: ../struct.PatternBuilder.html#method.build_literals
+    /// [`Pattern::find_iter_read`]: #method.find_iter_read
+    /// [status]: ../struct.Matcher.html#method.status
+    pub fn from_literals<I, L>(literals: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = L>,
+        L: AsRef<[u8]>,
+    {
+        PatternBuilder::new().build_literals(literals)
+    }
+
+    /// Escapes all regex meta characters in `text`, so that it is matched
+    /// literally when used as part of a pattern.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let host = "api.example.com";
+    /// let pattern = Pattern::new_anchored(&format!("https://{}/.*", Pattern::escape(host)))
+    ///     .expect("regex is not invalid");
+    ///
+    /// assert!(pattern.display_matches(&"https://api.example.com/users"));
+    /// assert!(!pattern.display_matches(&"https://apixexample.com/users"));
+    /// ```
+    pub fn escape(text: &str) -> String {
+        regex_syntax::escape(text)
+    }
+
+    /// Returns a new [`PatternBuilder`] for configuring how a `Pattern` is
+    /// compiled.
+    ///
+    /// [`PatternBuilder`]: ../struct.PatternBuilder.html
+    pub fn builder() -> PatternBuilder {
+        PatternBuilder::new()
+    }
+}
+
+impl Pattern<regex_automata::dfa::sparse::DFA<Vec<u8>>> {
+    /// Returns a new `Pattern` for the given regex, backed by a sparse DFA, or
+    /// an error if the regex was invalid.
+    ///
+    /// A dense DFA, as used by [`Pattern::new`], stores a transition for every
+    /// possible input byte in every state, which makes matching as fast as
+    /// possible but can use a lot of memory. A sparse DFA only stores the
+    /// transitions that actually lead somewhere, which makes it much smaller,
+    /// at the cost of slower matching. This makes sparse DFAs a good choice
+    /// when a large number of patterns must be kept in memory at once.
+    ///
+    /// As a rough guide, here are the sizes of some patterns' DFAs, and the
+    /// throughput of matching them against 1 MB of text, as measured on an
+    /// x86_64 Linux machine:
+    ///
+    /// | Pattern                          | Dense    | Sparse   | Dense speed | Sparse speed |
+    /// |----------------------------------|----------|----------|-------------|--------------|
+    /// | `hello world`                    | 3.4 KiB  | 0.7 KiB  | 320 MB/s    | 95 MB/s      |
+    /// | `[a-z]+@[a-z]+\.com`             | 1.3 KiB  | 0.5 KiB  | 320 MB/s    | 85 MB/s      |
+    /// | `(foo\|bar\|baz)[0-9]{1,4}-[a-z]+` | 3.8 KiB  | 1.0 KiB  | 360 MB/s    | 75 MB/s      |
+    /// | `\w+@\w+\.com` (Unicode)         | 640 KiB  | 228 KiB  | 335 MB/s    | 50 MB/s      |
+    ///
+    /// Otherwise, the returned `Pattern` behaves the same as one returned by
+    /// [`Pattern::new`]. Use [`PatternBuilder::build_sparse`] to configure the
+    /// pattern further.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let pattern = Pattern::new_sparse("a+b").expect("regex is not invalid");
+    ///
+    /// assert!(pattern.display_matches(&"hello world! aaaaab"));
+    /// assert!(!pattern.display_matches(&"hello world!"));
+    /// ```
+    ///
+    /// [`Pattern::new`]: #method.new
+    /// [`PatternBuilder::build_sparse`]: ../struct.PatternBuilder.html#method.build_sparse
+    pub fn new_sparse(pattern: &str) -> Result<Self, Error> {
+        PatternBuilder::new().build_sparse(pattern)
+    }
+}
+
+#[cfg(feature = "hybrid")]
+impl Pattern<regex_automata::hybrid::dfa::DFA> {
+    /// Returns a new `Pattern` for the given regex, backed by a lazy DFA, or
+    /// an error if the regex was invalid.
+    ///
+    /// A lazy DFA builds its states on demand as input is matched, rather
+    /// than compiling the whole DFA up front. This makes it a good choice for
+    /// patterns whose fully compiled DFAs would be very large, such as
+    /// patterns using large Unicode classes. Otherwise, the returned
+    /// `Pattern` behaves the same as one returned by [`Pattern::new`].
+    /// Use [`PatternBuilder::build_lazy`] to configure the pattern further.
+    ///
+    /// This method requires the `hybrid` feature flag.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let pattern = Pattern::new_lazy("a+b").expect("regex is not invalid");
+    ///
+    /// assert!(pattern.display_matches(&"hello world! aaaaab"));
+    /// assert!(!pattern.display_matches(&"hello world!"));
+    /// ```
+    ///
+    /// [`Pattern::new`]: #method.new
+    /// [`PatternBuilder::build_lazy`]: ../struct.PatternBuilder.html#method.build_lazy
+    pub fn new_lazy(pattern: &str) -> Result<Self, Error> {
+        PatternBuilder::new().build_lazy(pattern)
     }
 }
 
 impl FromStr for Pattern {
-    type Err = BuildError;
+    type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Self::new(s)
     }
 }
 
-impl<A: Automaton> Pattern<A> {
+impl<A: Backend> Pattern<A> {
     /// Obtains a `matcher` for this pattern.
     ///
     /// This conversion is useful when wanting to incrementally feed input (via
     /// `io::Write`/`fmt::Write` to a matcher). Otherwise, the convenience methods on Pattern
     /// suffice.
     pub fn matcher(&self) -> Matcher<&'_ A> {
-        let config = regex_automata::util::start::Config::new().anchored(self.anchored);
-        Matcher {
-            automaton: &self.automaton,
-            state: self.automaton.start_state(&config).unwrap(),
-        }
+        Matcher::new(&self.automaton, self.anchored)
+    }
+
+    /// Converts this pattern into a [`Matcher`] that owns its automaton.
+    ///
+    /// Unlike [`Pattern::matcher`], the returned matcher does not borrow the
+    /// pattern, so it may be stored in a struct or moved to another thread.
+    /// To obtain many owned matchers for the same pattern without copying
+    /// the automaton, use [`Pattern::into_shared`] first.
+    ///
+    /// [`Matcher`]: ../struct.Matcher.html
+    /// [`Pattern::matcher`]: #method.matcher
+    /// [`Pattern::into_shared`]: #method.into_shared
+    pub fn into_matcher(self) -> Matcher<A> {
+        Matcher::new(self.automaton, self.anchored)
     }
 
     /// Returns `true` if this pattern matches the given string.
@@ -172,7 +448,7 @@ impl<A: Automaton> Pattern<A> {
     /// assert!(pattern.debug_matches(&hello_world));
     ///
     /// let hello_sf = Hello { to: "San Francisco" };
-    /// assert_eq!(pattern.debug_matches(&hello_sf), false);
+    /// assert!(!pattern.debug_matches(&hello_sf));
     ///
     /// let hello_washington = Hello { to: "Washington" };
     /// assert!(pattern.debug_matches(&hello_washington));
@@ -205,10 +481,10 @@ impl<A: Automaton> Pattern<A> {
     ///
     /// let hello_world = Hello { to: "world" };
     /// assert!(pattern.display_matches(&hello_world));
-    /// assert_eq!(pattern.debug_matches(&hello_world), false);
+    /// assert!(!pattern.debug_matches(&hello_world));
     ///
     /// let hello_sf = Hello { to: "San Francisco" };
-    /// assert_eq!(pattern.display_matches(&hello_sf), false);
+    /// assert!(!pattern.display_matches(&hello_sf));
     ///
     /// let hello_washington = Hello { to: "Washington" };
     /// assert!(pattern.display_matches(&hello_washington));
@@ -222,98 +498,443 @@ impl<A: Automaton> Pattern<A> {
     /// data read from the provided `io::Read` stream, or an `io::Error` if an
     /// error occurred reading from the stream.
     #[inline]
+    #[cfg(feature = "std")]
     pub fn read_matches(&self, io: impl io::Read) -> io::Result<bool> {
         self.matcher().read_matches(io)
     }
+
+    /// Returns either a `bool` indicating whether or not this pattern matches the
+    /// data read from the provided `io::BufRead` stream, or an `io::Error` if an
+    /// error occurred reading from the stream.
+    ///
+    /// This avoids copying data that is already buffered by the reader, and
+    /// should be preferred over [`read_matches`] when an `io::BufRead` is
+    /// available.
+    ///
+    /// [`read_matches`]: #method.read_matches
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn read_matches_buf(&self, io: impl io::BufRead) -> io::Result<bool> {
+        self.matcher().read_matches_buf(io)
+    }
+}
+
+impl<A> Pattern<A> {
+    /// Converts this pattern into one whose automaton is shared behind an
+    /// `Arc`.
+    ///
+    /// Cloning a shared pattern is cheap, and [`Pattern::into_matcher`] on a
+    /// clone returns a `'static` matcher that can be sent to another thread
+    /// or task, such as a `Box<dyn io::Write + Send>` sink.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    /// use std::io::Write;
+    ///
+    /// let pattern = Pattern::new("hello world")
+    ///     .expect("regex is not invalid")
+    ///     .into_shared();
+    ///
+    /// let mut sink: Box<dyn Write + Send> = Box::new(pattern.clone().into_matcher());
+    /// let handle = std::thread::spawn(move || {
+    ///     sink.write_all(b"hello world").unwrap();
+    /// });
+    /// handle.join().unwrap();
+    ///
+    /// let mut matcher = pattern.into_matcher();
+    /// matcher.write_all(b"hello world").unwrap();
+    /// assert!(matcher.is_matched());
+    /// ```
+    ///
+    /// [`Pattern::into_matcher`]: #method.into_matcher
+    pub fn into_shared(self) -> Pattern<Arc<A>> {
+        Pattern {
+            automaton: Arc::new(self.automaton),
+            anchored: self.anchored,
+            captures: self.captures,
+        }
+    }
 }
 
 // === impl Matcher ===
 
 impl<A> Matcher<A>
 where
-    A: Automaton,
+    A: Backend,
 {
+    fn new(automaton: A, anchored: Anchored) -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let mut cache = automaton.create_cache();
+        let start = automaton.start_state(&mut cache, anchored);
+        Self {
+            automaton,
+            cache,
+            anchored,
+            start,
+            state: start,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            universal: None,
+            pos: 0,
+            match_end: None,
+        }
+    }
+
     #[inline]
     fn advance(&mut self, input: u8) {
-        // It's safe to call `next_state_unchecked` since the matcher may
-        // only be constructed by a `Pattern`, which, in turn, can only be
-        // constructed with a valid DFA.
-        self.state = unsafe { self.automaton.next_state_unchecked(self.state, input) };
+        self.state = self
+            .automaton
+            .next_state(&mut self.cache, self.state, input);
+        // Match states are delayed by one byte, so entering a match state
+        // here means that a match ended *before* the byte we just consumed.
+        if self.match_end.is_none() && self.automaton.is_match_state(self.state) {
+            self.match_end = Some(self.pos);
+        }
+        self.pos += 1;
+    }
+
+    /// Advances the automaton over a chunk of input, stopping early if it
+    /// enters a dead state. Returns the number of bytes consumed.
+    #[inline]
+    fn advance_bytes(&mut self, bytes: &[u8]) -> usize {
+        for (i, &byte) in bytes.iter().enumerate() {
+            self.advance(byte);
+            if self.automaton.is_dead_state(self.state) {
+                return i + 1;
+            }
+        }
+        bytes.len()
+    }
+
+    /// Rewinds this `Matcher` to the beginning of its input, so that it can be
+    /// reused to match another input.
+    ///
+    /// This is cheaper than obtaining a new matcher from the [`Pattern`], as
+    /// the start state is not recomputed.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    /// use std::fmt::Write;
+    ///
+    /// let pattern = Pattern::new_anchored("[0-9]+").unwrap();
+    /// let mut matcher = pattern.matcher();
+    /// let mut matched = 0;
+    /// for value in &[1, 22, -3, 444] {
+    ///     matcher.reset();
+    ///     // Writing fails early once the result is decided.
+    ///     let _ = write!(matcher, "{}", value);
+    ///     if matcher.is_matched() {
+    ///         matched += 1;
+    ///     }
+    /// }
+    /// assert_eq!(matched, 3);
+    /// ```
+    ///
+    /// [`Pattern`]: ../struct.Pattern.html
+    pub fn reset(&mut self) {
+        self.start = self
+            .automaton
+            .restart_state(&mut self.cache, self.start, self.anchored);
+        self.state = self.start;
+        self.pos = 0;
+        self.match_end = None;
+    }
+
+    /// Returns a snapshot of this `Matcher`'s progress through its input.
+    ///
+    /// The snapshot may later be passed to [`restore`] to rewind the matcher
+    /// to this point, so that several continuations of the same input can be
+    /// matched without feeding the common prefix more than once.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    /// use std::io::Write;
+    ///
+    /// let pattern = Pattern::new_anchored(r"GET /(index|about)\.html").unwrap();
+    /// let mut matcher = pattern.matcher();
+    /// matcher.write_all(b"GET /").unwrap();
+    /// let snapshot = matcher.state_snapshot();
+    ///
+    /// matcher.write_all(b"about.html").unwrap();
+    /// assert!(matcher.is_matched());
+    ///
+    /// matcher.restore(&snapshot);
+    /// matcher.write_all(b"contact.html").unwrap();
+    /// assert!(!matcher.is_matched());
+    /// ```
+    ///
+    /// [`restore`]: #method.restore
+    pub fn state_snapshot(&self) -> MatcherSnapshot<A> {
+        MatcherSnapshot {
+            cache: self.cache.clone(),
+            state: self.state,
+            pos: self.pos,
+            match_end: self.match_end,
+            id: self.id,
+        }
+    }
+
+    /// Rewinds this `Matcher` to the point at which `snapshot` was taken.
+    ///
+    /// # Panics
+    ///
+    /// If `snapshot` was not taken from this matcher, or from the matcher it
+    /// was cloned from.
+    pub fn restore(&mut self, snapshot: &MatcherSnapshot<A>) {
+        assert_eq!(
+            self.id, snapshot.id,
+            "snapshot was taken from a different matcher"
+        );
+        self.cache.clone_from(&snapshot.cache);
+        self.state = snapshot.state;
+        self.pos = snapshot.pos;
+        self.match_end = snapshot.match_end;
     }
 
     /// Returns `true` if this `Matcher` has matched any input that has been
     /// provided.
     #[inline]
     pub fn is_matched(&self) -> bool {
-        let eoi_state = self.automaton.next_eoi_state(self.state);
+        let eoi_state = self.automaton.next_eoi_state(&self.cache, self.state);
         self.automaton.is_match_state(eoi_state)
     }
 
+    /// Returns whether providing more input to this `Matcher` could change
+    /// whether it matches.
+    ///
+    /// Once this returns [`MatchStatus::Matched`] or
+    /// [`MatchStatus::Rejected`], the result of [`is_matched`] is final, so a
+    /// producer may stop formatting or reading its input early.
+    ///
+    /// `Matched` is only returned if *any* sequence of bytes may follow, so a
+    /// pattern can only be decided early if it matches arbitrary bytes, and
+    /// not just valid UTF-8. For example, `(?s-u:error:.*)` built with
+    /// [`PatternBuilder::utf8`] disabled may be decided early, while
+    /// `(?s)error:.*` remains pending since an invalid UTF-8 byte would end the
+    /// match. Detecting this requires exploring the states of the automaton,
+    /// and is only done when the input so far matches. Lazy DFAs (with the
+    /// `hybrid` feature) never explore states that have not been built yet,
+    /// and so report [`MatchStatus::Pending`] rather than `Matched` until the
+    /// input ends.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::{MatchStatus, Pattern};
+    /// use std::fmt::Write;
+    ///
+    /// let pattern = Pattern::builder()
+    ///     .utf8(false)
+    ///     .build(r"(?s-u:error:.*)")
+    ///     .unwrap();
+    /// let mut matcher = pattern.matcher();
+    /// write!(matcher, "an error").unwrap();
+    /// assert_eq!(matcher.status(), MatchStatus::Pending);
+    /// // Once the result is decided, writing returns an error.
+    /// assert!(write!(matcher, ": something went wrong").is_err());
+    /// assert_eq!(matcher.status(), MatchStatus::Matched);
+    ///
+    /// let pattern = Pattern::new_anchored("error").unwrap();
+    /// let mut matcher = pattern.matcher();
+    /// assert!(write!(matcher, "warning").is_err());
+    /// assert_eq!(matcher.status(), MatchStatus::Rejected);
+    /// ```
+    ///
+    /// [`MatchStatus::Matched`]: enum.MatchStatus.html#variant.Matched
+    /// [`MatchStatus::Rejected`]: enum.MatchStatus.html#variant.Rejected
+    /// [`MatchStatus::Pending`]: enum.MatchStatus.html#variant.Pending
+    /// [`is_matched`]: #method.is_matched
+    /// [`PatternBuilder::utf8`]: struct.PatternBuilder.html#method.utf8
+    pub fn status(&self) -> MatchStatus {
+        if self.automaton.is_dead_state(self.state) {
+            return MatchStatus::Rejected;
+        }
+        let universal = match self.universal {
+            Some((state, universal)) if state == self.state => universal,
+            _ => self.is_universal(),
+        };
+        if universal {
+            MatchStatus::Matched
+        } else {
+            MatchStatus::Pending
+        }
+    }
+
+    /// Returns `true` if the result of this matcher can no longer change,
+    /// remembering the result for the current state.
+    fn is_decided(&mut self) -> bool {
+        if self.automaton.is_dead_state(self.state) {
+            return true;
+        }
+        match self.universal {
+            Some((state, universal)) if state == self.state => universal,
+            _ => {
+                let universal = self.is_universal();
+                self.universal = Some((self.state, universal));
+                universal
+            }
+        }
+    }
+
+    fn is_universal(&self) -> bool {
+        self.is_matched() && self.automaton.is_universal_state(&self.cache, self.state)
+    }
+
+    /// Returns the number of bytes of input that have been provided to this
+    /// `Matcher` so far.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Returns the byte offset at which the first match in the input ended,
+    /// or `None` if nothing has matched yet.
+    ///
+    /// This is the earliest point at which the underlying automaton entered
+    /// a match state, counted across every call to `write` or `write_str`. If
+    /// the pattern contains repetitions, this is where the *shortest* match
+    /// ended; for example, `a+` first matches after a single `a`.
+    ///
+    /// Note that this may return `Some` even if [`is_matched`] returns
+    /// `false`: a pattern only matches an input if the match extends all the
+    /// way to the end of the input, but the first match may end earlier.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    /// use std::io::Write;
+    ///
+    /// let pattern = Pattern::new("error").unwrap();
+    /// let mut matcher = pattern.matcher();
+    ///
+    /// matcher.write_all(b"info: all good\n").unwrap();
+    /// assert_eq!(matcher.first_match_end(), None);
+    ///
+    /// matcher.write_all(b"err").unwrap();
+    /// matcher.write_all(b"or: oh no").unwrap();
+    /// assert_eq!(matcher.first_match_end(), Some(20));
+    /// assert_eq!(matcher.position(), 27);
+    /// ```
+    ///
+    /// [`is_matched`]: #method.is_matched
+    pub fn first_match_end(&self) -> Option<u64> {
+        self.match_end.or_else(|| {
+            let eoi_state = self.automaton.next_eoi_state(&self.cache, self.state);
+            if self.automaton.is_match_state(eoi_state) {
+                Some(self.pos)
+            } else {
+                None
+            }
+        })
+    }
+
     /// Returns `true` if this pattern matches the formatted output of the given
     /// type implementing `fmt::Debug`.
     pub fn matches(mut self, s: &impl AsRef<str>) -> bool {
-        for &byte in s.as_ref().as_bytes() {
-            self.advance(byte);
-            if self.automaton.is_dead_state(self.state) {
-                return false;
-            }
-        }
+        self.advance_bytes(s.as_ref().as_bytes());
         self.is_matched()
     }
 
     /// Returns `true` if this pattern matches the formatted output of the given
     /// type implementing `fmt::Debug`.
     pub fn debug_matches(mut self, d: &impl fmt::Debug) -> bool {
-        use std::fmt::Write;
-        write!(&mut self, "{:?}", d).expect("matcher write impl should not fail");
+        use core::fmt::Write;
+        // An error means that the result was decided before `d` was fully
+        // formatted, so the rest of its output can be ignored.
+        let _ = write!(&mut self, "{:?}", d);
         self.is_matched()
     }
 
     /// Returns `true` if this pattern matches the formatted output of the given
     /// type implementing `fmt::Display`.
     pub fn display_matches(mut self, d: &impl fmt::Display) -> bool {
-        use std::fmt::Write;
-        write!(&mut self, "{}", d).expect("matcher write impl should not fail");
+        use core::fmt::Write;
+        // An error means that the result was decided before `d` was fully
+        // formatted, so the rest of its output can be ignored.
+        let _ = write!(&mut self, "{}", d);
         self.is_matched()
     }
 
     /// Returns either a `bool` indicating whether or not this pattern matches the
     /// data read from the provided `io::Read` stream, or an `io::Error` if an
     /// error occurred reading from the stream.
-    pub fn read_matches(mut self, io: impl io::Read + Sized) -> io::Result<bool> {
-        for r in io.bytes() {
-            self.advance(r?);
+    ///
+    /// The stream is read in chunks into a fixed-size buffer on the stack, so
+    /// there is no need to wrap unbuffered readers in an `io::BufReader`.
+    #[cfg(feature = "std")]
+    pub fn read_matches(mut self, mut io: impl io::Read + Sized) -> io::Result<bool> {
+        let mut buf = [0u8; READ_BUF_LEN];
+        loop {
+            let n = match io.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.advance_bytes(&buf[..n]);
             if self.automaton.is_dead_state(self.state) {
                 return Ok(false);
             }
         }
         Ok(self.is_matched())
     }
-}
 
-impl<A: Automaton> fmt::Write for Matcher<A> {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        for &byte in s.as_bytes() {
-            self.advance(byte);
+    /// Returns either a `bool` indicating whether or not this pattern matches the
+    /// data read from the provided `io::BufRead` stream, or an `io::Error` if an
+    /// error occurred reading from the stream.
+    ///
+    /// Unlike [`read_matches`], this matches the reader's own buffer directly,
+    /// rather than copying the data into a separate buffer first.
+    ///
+    /// [`read_matches`]: #method.read_matches
+    #[cfg(feature = "std")]
+    pub fn read_matches_buf(mut self, mut io: impl io::BufRead + Sized) -> io::Result<bool> {
+        loop {
+            let buf = match io.fill_buf() {
+                Ok([]) => break,
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let n = self.advance_bytes(buf);
+            io.consume(n);
             if self.automaton.is_dead_state(self.state) {
-                break;
+                return Ok(false);
             }
         }
-        Ok(())
+        Ok(self.is_matched())
     }
 }
 
-impl<A: Automaton> io::Write for Matcher<A> {
-    fn write(&mut self, bytes: &[u8]) -> Result<usize, io::Error> {
-        let mut i = 0;
-        for &byte in bytes {
-            self.advance(byte);
-            i += 1;
-            if self.automaton.is_dead_state(self.state) {
-                break;
-            }
+/// Writing to a `Matcher` returns an error once further input can no longer
+/// change whether it matches (see [`Matcher::status`]), so that formatting
+/// can stop early.
+///
+/// [`Matcher::status`]: struct.Matcher.html#method.status
+impl<A: Backend> fmt::Write for Matcher<A> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let n = if self.is_decided() {
+            0
+        } else {
+            self.advance_bytes(s.as_bytes())
+        };
+        // Once the result is decided, the rest of the input can't change it,
+        // but it still counts towards the position in the stream.
+        self.pos += (s.len() - n) as u64;
+        if self.is_decided() {
+            Err(fmt::Error)
+        } else {
+            Ok(())
         }
-        Ok(i)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Backend> io::Write for Matcher<A> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, io::Error> {
+        Ok(self.advance_bytes(bytes))
     }
 
     fn flush(&mut self) -> Result<(), io::Error> {
@@ -357,12 +978,14 @@ mod test {
             Str(s)
         }
 
-        fn to_reader(self) -> ReadStr<'a> {
+        fn into_reader(self) -> ReadStr<'a> {
             ReadStr(io::Cursor::new(self.0.as_bytes()))
         }
     }
 
-    fn test_debug_matches(new_pattern: impl Fn(&str) -> Result<Pattern, BuildError>) {
+    fn test_debug_matches<A: Backend, E: fmt::Debug>(
+        new_pattern: impl Fn(&str) -> Result<Pattern<A>, E>,
+    ) {
         let pat = new_pattern("hello world").unwrap();
         assert!(pat.debug_matches(&Str::hello_world()));
 
@@ -370,10 +993,12 @@ mod test {
         assert!(pat.debug_matches(&Str::hello_world()));
 
         let pat = new_pattern("goodbye world").unwrap();
-        assert_eq!(pat.debug_matches(&Str::hello_world()), false);
+        assert!(!pat.debug_matches(&Str::hello_world()));
     }
 
-    fn test_display_matches(new_pattern: impl Fn(&str) -> Result<Pattern, BuildError>) {
+    fn test_display_matches<A: Backend, E: fmt::Debug>(
+        new_pattern: impl Fn(&str) -> Result<Pattern<A>, E>,
+    ) {
         let pat = new_pattern("hello world").unwrap();
         assert!(pat.display_matches(&Str::hello_world()));
 
@@ -381,36 +1006,166 @@ mod test {
         assert!(pat.display_matches(&Str::hello_world()));
 
         let pat = new_pattern("goodbye world").unwrap();
-        assert_eq!(pat.display_matches(&Str::hello_world()), false);
+        assert!(!pat.display_matches(&Str::hello_world()));
     }
 
-    fn test_reader_matches(new_pattern: impl Fn(&str) -> Result<Pattern, BuildError>) {
+    fn test_reader_matches<A: Backend, E: fmt::Debug>(
+        new_pattern: impl Fn(&str) -> Result<Pattern<A>, E>,
+    ) {
         let pat = new_pattern("hello world").unwrap();
         assert!(pat
-            .read_matches(Str::hello_world().to_reader())
+            .read_matches(Str::hello_world().into_reader())
             .expect("no io error should occur"));
 
         let pat = new_pattern("hel+o w[orl]{3}d").unwrap();
         assert!(pat
-            .read_matches(Str::hello_world().to_reader())
+            .read_matches(Str::hello_world().into_reader())
             .expect("no io error should occur"));
 
         let pat = new_pattern("goodbye world").unwrap();
-        assert_eq!(
-            pat.read_matches(Str::hello_world().to_reader())
-                .expect("no io error should occur"),
-            false
-        );
+        assert!(!pat
+            .read_matches(Str::hello_world().into_reader())
+            .expect("no io error should occur"));
     }
 
-    fn test_debug_rep_patterns(new_pattern: impl Fn(&str) -> Result<Pattern, BuildError>) {
+    fn test_bufreader_matches<A: Backend, E: fmt::Debug>(
+        new_pattern: impl Fn(&str) -> Result<Pattern<A>, E>,
+    ) {
+        // Use a tiny buffer so that the input is split across many chunks.
+        let reader = |s| io::BufReader::with_capacity(3, Str::new(s).into_reader());
+
+        let pat = new_pattern("hello world").unwrap();
+        assert!(pat
+            .read_matches_buf(reader("hello world"))
+            .expect("no io error should occur"));
+
+        let pat = new_pattern("hel+o w[orl]{3}d").unwrap();
+        assert!(pat
+            .read_matches_buf(reader("hello world"))
+            .expect("no io error should occur"));
+
+        let pat = new_pattern("goodbye world").unwrap();
+        assert!(!pat
+            .read_matches_buf(reader("hello world"))
+            .expect("no io error should occur"));
+    }
+
+    fn test_reset<A: Backend, E: fmt::Debug>(new_pattern: impl Fn(&str) -> Result<Pattern<A>, E>) {
+        use core::fmt::Write;
+
+        let pat = new_pattern("a+b").unwrap();
+        let mut matcher = pat.matcher();
+        matcher.write_str("aab").unwrap();
+        assert!(matcher.is_matched());
+        assert_eq!(matcher.first_match_end(), Some(3));
+
+        matcher.reset();
+        assert!(!matcher.is_matched());
+        assert_eq!(matcher.position(), 0);
+        assert_eq!(matcher.first_match_end(), None);
+
+        matcher.write_str("abb").unwrap();
+        assert!(!matcher.is_matched());
+        matcher.reset();
+        matcher.write_str("ab").unwrap();
+        assert!(matcher.is_matched());
+    }
+
+    fn test_snapshot<A: Backend, E: fmt::Debug>(
+        new_pattern: impl Fn(&str) -> Result<Pattern<A>, E>,
+    ) {
+        use core::fmt::Write;
+
+        let pat = new_pattern("hello (world|there)").unwrap();
+        let mut matcher = pat.matcher();
+        matcher.write_str("hello ").unwrap();
+        let snapshot = matcher.state_snapshot();
+
+        matcher.write_str("world").unwrap();
+        assert!(matcher.is_matched());
+
+        matcher.restore(&snapshot);
+        assert_eq!(matcher.position(), 6);
+        // Anchored patterns are rejected here, but unanchored ones aren't.
+        let _ = matcher.write_str("moon");
+        assert!(!matcher.is_matched());
+
+        let mut branch = matcher.clone();
+        branch.restore(&snapshot);
+        branch.write_str("there").unwrap();
+        assert!(branch.is_matched());
+        assert_eq!(branch.first_match_end(), Some(11));
+    }
+
+    fn test_status<A: Backend, E: fmt::Debug>(new_pattern: impl Fn(&str) -> Result<Pattern<A>, E>) {
+        use core::fmt::Write;
+
+        let pat = new_pattern("(?s-u:hello.*)").unwrap();
+        let mut matcher = pat.matcher();
+        assert_eq!(matcher.status(), MatchStatus::Pending);
+        matcher.write_str("hell").unwrap();
+        assert_eq!(matcher.status(), MatchStatus::Pending);
+        // Writing returns an error once the result is decided.
+        assert!(matcher.write_str("o").is_err());
+        assert_eq!(matcher.status(), MatchStatus::Matched);
+        assert!(matcher.write_str(" world\n").is_err());
+        assert_eq!(matcher.status(), MatchStatus::Matched);
+        assert!(matcher.is_matched());
+
+        // An invalid UTF-8 byte would end a Unicode `.*`.
+        let pat = new_pattern("(?s)hello.*").unwrap();
+        let mut matcher = pat.matcher();
+        matcher.write_str("hello").unwrap();
+        assert!(matcher.is_matched());
+        assert_eq!(matcher.status(), MatchStatus::Pending);
+        io::Write::write_all(&mut matcher, b"\xFF").unwrap();
+        assert!(!matcher.is_matched());
+
+        // A match that could still be broken by further input is pending.
+        let pat = new_pattern("hello").unwrap();
+        let mut matcher = pat.matcher();
+        matcher.write_str("hello").unwrap();
+        assert!(matcher.is_matched());
+        assert_eq!(matcher.status(), MatchStatus::Pending);
+        assert!(matcher.write_str(" world").is_err());
+        assert_eq!(matcher.status(), MatchStatus::Rejected);
+        assert!(!matcher.is_matched());
+    }
+
+    fn test_first_match_end<A: Backend, E: fmt::Debug>(
+        new_pattern: impl Fn(&str) -> Result<Pattern<A>, E>,
+    ) {
+        use core::fmt::Write;
+
+        let pat = new_pattern("hello").unwrap();
+        let mut matcher = pat.matcher();
+        assert_eq!(matcher.first_match_end(), None);
+        matcher.write_str("hel").unwrap();
+        assert_eq!(matcher.first_match_end(), None);
+        matcher.write_str("lo").unwrap();
+        // The match ends at the end of the input so far.
+        assert_eq!(matcher.first_match_end(), Some(5));
+        assert!(matcher.write_str(" world").is_err());
+        assert_eq!(matcher.first_match_end(), Some(5));
+        assert_eq!(matcher.position(), 11);
+
+        let pat = new_pattern("goodbye").unwrap();
+        let mut matcher = pat.matcher();
+        // Anchored patterns are rejected after the first byte.
+        let _ = matcher.write_str("hello world");
+        assert_eq!(matcher.first_match_end(), None);
+    }
+
+    fn test_debug_rep_patterns<A: Backend, E: fmt::Debug>(
+        new_pattern: impl Fn(&str) -> Result<Pattern<A>, E>,
+    ) {
         let pat = new_pattern("a+b").unwrap();
         assert!(pat.debug_matches(&Str::new("ab")));
         assert!(pat.debug_matches(&Str::new("aaaab")));
         assert!(pat.debug_matches(&Str::new("aaaaaaaaaab")));
-        assert_eq!(pat.debug_matches(&Str::new("b")), false);
-        assert_eq!(pat.debug_matches(&Str::new("abb")), false);
-        assert_eq!(pat.debug_matches(&Str::new("aaaaabb")), false);
+        assert!(!pat.debug_matches(&Str::new("b")));
+        assert!(!pat.debug_matches(&Str::new("abb")));
+        assert!(!pat.debug_matches(&Str::new("aaaaabb")));
     }
 
     mod anchored {
@@ -430,11 +1185,36 @@ mod test {
             test_reader_matches(Pattern::new_anchored)
         }
 
+        #[test]
+        fn bufreader_matches() {
+            test_bufreader_matches(Pattern::new_anchored)
+        }
+
         #[test]
         fn debug_rep_patterns() {
             test_debug_rep_patterns(Pattern::new_anchored)
         }
 
+        #[test]
+        fn first_match_end() {
+            test_first_match_end(Pattern::new_anchored)
+        }
+
+        #[test]
+        fn status() {
+            test_status(|p| PatternBuilder::new().utf8(false).anchored(true).build(p));
+        }
+
+        #[test]
+        fn reset() {
+            test_reset(Pattern::new_anchored);
+        }
+
+        #[test]
+        fn snapshot() {
+            test_snapshot(Pattern::new_anchored);
+        }
+
         // === anchored behavior =============================================
         // Tests that anchored patterns match each input type only beginning at
         // the first character.
@@ -461,7 +1241,7 @@ mod test {
         #[test]
         fn reader_is_anchored() {
             test_is_anchored(|pat, input| {
-                pat.read_matches(input.to_reader())
+                pat.read_matches(input.into_reader())
                     .expect("no io error occurs")
             });
         }
@@ -492,7 +1272,7 @@ mod test {
         #[test]
         fn reader_explicitly_unanchored() {
             test_explicitly_unanchored(|pat, input| {
-                pat.read_matches(input.to_reader())
+                pat.read_matches(input.into_reader())
                     .expect("no io error occurs")
             });
         }
@@ -515,11 +1295,113 @@ mod test {
             test_reader_matches(Pattern::new)
         }
 
+        #[test]
+        fn bufreader_matches() {
+            test_bufreader_matches(Pattern::new)
+        }
+
         #[test]
         fn debug_rep_patterns() {
             test_debug_rep_patterns(Pattern::new)
         }
 
+        #[test]
+        fn reader_matches_across_chunks() {
+            let pat = Pattern::new("a+b").unwrap();
+            let mut input = "x".repeat(READ_BUF_LEN * 2 + 5);
+            input.push_str("aaab");
+            assert!(pat.read_matches(input.as_bytes()).unwrap());
+            assert!(pat.read_matches_buf(input.as_bytes()).unwrap());
+
+            input.push('x');
+            assert!(!pat.read_matches(input.as_bytes()).unwrap());
+            assert!(!pat.read_matches_buf(input.as_bytes()).unwrap());
+        }
+
+        #[test]
+        fn first_match_end() {
+            test_first_match_end(Pattern::new)
+        }
+
+        #[test]
+        fn status() {
+            test_status(|p| PatternBuilder::new().utf8(false).build(p));
+        }
+
+        #[test]
+        fn debug_stops_formatting_once_decided() {
+            use std::cell::Cell;
+
+            struct Counted<'a>(&'a Cell<usize>);
+            impl fmt::Debug for Counted<'_> {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    self.0.set(self.0.get() + 1);
+                    f.write_str("item")
+                }
+            }
+
+            let formatted = Cell::new(0);
+            let items: Vec<_> = (0..100).map(|_| Counted(&formatted)).collect();
+
+            let pat = Pattern::new_anchored("hello").unwrap();
+            assert!(!pat.debug_matches(&items));
+            assert_eq!(formatted.get(), 0);
+
+            let pat = PatternBuilder::new()
+                .utf8(false)
+                .build(r"(?s-u:\[item.*)")
+                .unwrap();
+            assert!(pat.debug_matches(&items));
+            assert_eq!(formatted.get(), 1);
+        }
+
+        #[test]
+        fn status_is_pending_until_match() {
+            use core::fmt::Write;
+
+            let pat = Pattern::new("a+b").unwrap();
+            let mut matcher = pat.matcher();
+            matcher.write_str("qqq").unwrap();
+            assert_eq!(matcher.status(), MatchStatus::Pending);
+
+            let pat = Pattern::new_anchored("a+b").unwrap();
+            let mut matcher = pat.matcher();
+            assert!(matcher.write_str("qqq").is_err());
+            assert_eq!(matcher.status(), MatchStatus::Rejected);
+        }
+
+        #[test]
+        fn reset() {
+            test_reset(Pattern::new);
+        }
+
+        #[test]
+        fn snapshot() {
+            test_snapshot(Pattern::new);
+        }
+
+        #[test]
+        #[should_panic(expected = "snapshot was taken from a different matcher")]
+        fn restore_foreign_snapshot() {
+            let hello = Pattern::new("hello").unwrap();
+            let goodbye = Pattern::new("goodbye").unwrap();
+            let snapshot = hello.matcher().state_snapshot();
+            goodbye.matcher().restore(&snapshot);
+        }
+
+        #[test]
+        fn first_match_end_across_writes() {
+            use std::io::Write;
+
+            let pat = Pattern::new("b+").unwrap();
+            let mut matcher = pat.matcher();
+            matcher.write_all(b"aaaa").unwrap();
+            matcher.write_all(b"ab").unwrap();
+            assert_eq!(matcher.first_match_end(), Some(6));
+            matcher.write_all(b"bbb").unwrap();
+            assert_eq!(matcher.first_match_end(), Some(6));
+        }
+
         // === anchored behavior =============================================
         // Tests that unanchored patterns match anywhere in the input stream.
         fn test_is_unanchored(f: impl Fn(&Pattern, Str) -> bool) {
@@ -545,9 +1427,275 @@ mod test {
         #[test]
         fn reader_is_unanchored() {
             test_is_unanchored(|pat, input| {
-                pat.read_matches(input.to_reader())
+                pat.read_matches(input.into_reader())
                     .expect("no io error occurs")
             });
         }
     }
+
+    mod sparse {
+        use super::*;
+
+        fn new_sparse_anchored(
+            pattern: &str,
+        ) -> Result<Pattern<regex_automata::dfa::sparse::DFA<Vec<u8>>>, Error> {
+            PatternBuilder::new().anchored(true).build_sparse(pattern)
+        }
+
+        #[test]
+        fn debug_matches() {
+            test_debug_matches(Pattern::new_sparse);
+            test_debug_matches(new_sparse_anchored);
+        }
+
+        #[test]
+        fn display_matches() {
+            test_display_matches(Pattern::new_sparse);
+            test_display_matches(new_sparse_anchored);
+        }
+
+        #[test]
+        fn reader_matches() {
+            test_reader_matches(Pattern::new_sparse);
+            test_reader_matches(new_sparse_anchored);
+        }
+
+        #[test]
+        fn debug_rep_patterns() {
+            test_debug_rep_patterns(Pattern::new_sparse);
+            test_debug_rep_patterns(new_sparse_anchored);
+        }
+
+        #[test]
+        fn first_match_end() {
+            test_first_match_end(Pattern::new_sparse);
+            test_first_match_end(new_sparse_anchored);
+        }
+
+        #[test]
+        fn status() {
+            test_status(|p| PatternBuilder::new().utf8(false).build_sparse(p));
+            test_status(|p| {
+                PatternBuilder::new()
+                    .utf8(false)
+                    .anchored(true)
+                    .build_sparse(p)
+            });
+        }
+
+        #[test]
+        fn reset() {
+            test_reset(Pattern::new_sparse);
+            test_reset(new_sparse_anchored);
+        }
+
+        #[test]
+        fn snapshot() {
+            test_snapshot(Pattern::new_sparse);
+            test_snapshot(new_sparse_anchored);
+        }
+
+        #[test]
+        fn is_anchored() {
+            let pat = new_sparse_anchored("a+b").unwrap();
+            assert!(pat.display_matches(&"aaab"));
+            assert!(!pat.display_matches(&"ffab"));
+
+            let pat = Pattern::new_sparse("a+b").unwrap();
+            assert!(pat.display_matches(&"ffab"));
+        }
+    }
+
+    mod shared {
+        use super::*;
+
+        fn new_shared(pattern: &str) -> Result<Pattern<Arc<DFA<Vec<u32>>>>, Error> {
+            Pattern::new(pattern).map(Pattern::into_shared)
+        }
+
+        fn new_shared_anchored(pattern: &str) -> Result<Pattern<Arc<DFA<Vec<u32>>>>, Error> {
+            Pattern::new_anchored(pattern).map(Pattern::into_shared)
+        }
+
+        #[test]
+        fn debug_matches() {
+            test_debug_matches(new_shared);
+            test_debug_matches(new_shared_anchored);
+        }
+
+        #[test]
+        fn display_matches() {
+            test_display_matches(new_shared);
+            test_display_matches(new_shared_anchored);
+        }
+
+        #[test]
+        fn reader_matches() {
+            test_reader_matches(new_shared);
+            test_reader_matches(new_shared_anchored);
+        }
+
+        #[test]
+        fn first_match_end() {
+            test_first_match_end(new_shared);
+            test_first_match_end(new_shared_anchored);
+        }
+
+        #[test]
+        fn status() {
+            test_status(|p| {
+                PatternBuilder::new()
+                    .utf8(false)
+                    .build(p)
+                    .map(Pattern::into_shared)
+            });
+            test_status(|p| {
+                PatternBuilder::new()
+                    .utf8(false)
+                    .anchored(true)
+                    .build(p)
+                    .map(Pattern::into_shared)
+            });
+        }
+
+        #[test]
+        fn reset() {
+            test_reset(new_shared);
+            test_reset(new_shared_anchored);
+        }
+
+        #[test]
+        fn snapshot() {
+            test_snapshot(new_shared);
+            test_snapshot(new_shared_anchored);
+        }
+
+        #[test]
+        fn owned_matcher_is_static_and_send() {
+            fn assert_static_send<T: 'static + Send>(_: &T) {}
+
+            let pattern = new_shared("hello world").unwrap();
+            let matcher = pattern.clone().into_matcher();
+            assert_static_send(&matcher);
+
+            let mut sink: Box<dyn io::Write + Send> = Box::new(matcher);
+            std::thread::spawn(move || sink.write_all(b"hello world"))
+                .join()
+                .unwrap()
+                .unwrap();
+
+            let mut matcher = pattern.into_matcher();
+            io::Write::write_all(&mut matcher, b"hello world").unwrap();
+            assert!(matcher.is_matched());
+        }
+
+        #[test]
+        fn into_matcher() {
+            let mut matcher = Pattern::new("a+b").unwrap().into_matcher();
+            io::Write::write_all(&mut matcher, b"ffaab").unwrap();
+            assert!(matcher.is_matched());
+        }
+    }
+
+    #[cfg(feature = "hybrid")]
+    mod lazy {
+        use super::*;
+
+        fn new_lazy_anchored(
+            pattern: &str,
+        ) -> Result<Pattern<regex_automata::hybrid::dfa::DFA>, Error> {
+            PatternBuilder::new().anchored(true).build_lazy(pattern)
+        }
+
+        #[test]
+        fn debug_matches() {
+            test_debug_matches(Pattern::new_lazy);
+            test_debug_matches(new_lazy_anchored);
+        }
+
+        #[test]
+        fn display_matches() {
+            test_display_matches(Pattern::new_lazy);
+            test_display_matches(new_lazy_anchored);
+        }
+
+        #[test]
+        fn reader_matches() {
+            test_reader_matches(Pattern::new_lazy);
+            test_reader_matches(new_lazy_anchored);
+        }
+
+        #[test]
+        fn bufreader_matches() {
+            test_bufreader_matches(Pattern::new_lazy);
+            test_bufreader_matches(new_lazy_anchored);
+        }
+
+        #[test]
+        fn debug_rep_patterns() {
+            test_debug_rep_patterns(Pattern::new_lazy);
+            test_debug_rep_patterns(new_lazy_anchored);
+        }
+
+        #[test]
+        fn first_match_end() {
+            test_first_match_end(Pattern::new_lazy);
+            test_first_match_end(new_lazy_anchored);
+        }
+
+        #[test]
+        fn status_is_never_matched_early() {
+            use core::fmt::Write;
+
+            let pat = PatternBuilder::new()
+                .utf8(false)
+                .build_lazy("(?s-u:hello.*)")
+                .unwrap();
+            let mut matcher = pat.matcher();
+            matcher.write_str("hello").unwrap();
+            assert!(matcher.is_matched());
+            assert_eq!(matcher.status(), MatchStatus::Pending);
+
+            let pat = new_lazy_anchored("hello").unwrap();
+            let mut matcher = pat.matcher();
+            assert!(matcher.write_str("goodbye").is_err());
+            assert_eq!(matcher.status(), MatchStatus::Rejected);
+        }
+
+        #[test]
+        fn reset() {
+            test_reset(Pattern::new_lazy);
+            test_reset(new_lazy_anchored);
+        }
+
+        #[test]
+        fn snapshot() {
+            test_snapshot(Pattern::new_lazy);
+            test_snapshot(new_lazy_anchored);
+        }
+
+        #[test]
+        fn is_anchored() {
+            let pat = new_lazy_anchored("a+b").unwrap();
+            assert!(pat.display_matches(&"aaab"));
+            assert!(!pat.display_matches(&"ffab"));
+
+            let pat = Pattern::new_lazy("a+b").unwrap();
+            assert!(pat.display_matches(&"ffab"));
+        }
+
+        #[test]
+        #[cfg(feature = "unicode")]
+        fn large_unicode_class() {
+            // This pattern's dense DFA is enormous, but the lazy DFA only
+            // builds the states that the input actually reaches.
+            let pat = PatternBuilder::new()
+                .unicode(true)
+                .build_lazy(r"[\p{L}]{50}")
+                .unwrap();
+            let input = "é".repeat(50);
+            assert!(pat.display_matches(&input));
+            assert!(!pat.display_matches(&"é"));
+        }
+    }
 }
@@ -0,0 +1,384 @@
+use crate::{Backend, Matcher, Pattern};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use regex_automata::dfa::dense::DFA;
+
+/// Matches each line of input provided incrementally via `fmt::Write` or
+/// `io::Write` separately, recording the lines that match.
+///
+/// A `LineMatcher` is returned by [`Pattern::line_matcher`]. Every time a
+/// `\n` is written, the line before it is matched as if it were the whole
+/// input (in the same way as [`Pattern::matches`]), and the matcher is reset
+/// to the start of the pattern for the next line. This means that an
+/// anchored pattern is anchored at the start of each line, rather than only
+/// at the start of the input.
+///
+/// Lines are numbered from 0, and the `\n` is not part of the line it ends.
+/// If [`crlf`] is enabled, a `\r` immediately before the `\n` is not part of
+/// the line either.
+///
+/// For example:
+/// ```
+/// use matchers::Pattern;
+/// use std::io::Write;
+///
+/// let pattern = Pattern::new_anchored("error: .*").expect("regex is not invalid");
+/// let mut matcher = pattern.line_matcher();
+/// matcher.write_all(b"info: starting\nerror: oh no\n").unwrap();
+/// matcher.write_all(b"warn: hmm\nerror: not again").unwrap();
+/// assert_eq!(matcher.matched_lines(), &[1]);
+///
+/// // The last line has no `\n` after it, so it's only matched once the
+/// // input is finished.
+/// assert_eq!(matcher.finish(), vec![1, 3]);
+/// ```
+///
+/// [`Pattern::line_matcher`]: ../struct.Pattern.html#method.line_matcher
+/// [`Pattern::matches`]: ../struct.Pattern.html#method.matches
+/// [`crlf`]: #method.crlf
+#[derive(Debug, Clone)]
+pub struct LineMatcher<A: Backend = DFA<Vec<u32>>> {
+    matcher: Matcher<A>,
+    crlf: bool,
+    /// The index of the line currently being matched.
+    line: u64,
+    /// Whether any bytes of the current line have been written.
+    in_line: bool,
+    /// Whether the last byte written was a `\r` that hasn't been matched yet,
+    /// because it may turn out to be part of a `\r\n` line terminator.
+    pending_cr: bool,
+    matched: Vec<u64>,
+}
+
+/// An iterator over the lines of an `io::BufRead` stream that match a
+/// [`Pattern`].
+///
+/// This is returned by [`Pattern::read_matching_lines`].
+///
+/// [`Pattern`]: ../struct.Pattern.html
+/// [`Pattern::read_matching_lines`]: ../struct.Pattern.html#method.read_matching_lines
+#[derive(Debug)]
+#[cfg(feature = "std")]
+pub struct MatchingLines<'a, R, A: Backend = DFA<Vec<u32>>> {
+    matcher: Matcher<&'a A>,
+    reader: R,
+    crlf: bool,
+    line: u64,
+    buf: Vec<u8>,
+}
+
+// === impl Pattern ===
+
+impl<A: Backend> Pattern<A> {
+    /// Returns a [`LineMatcher`] that matches each line of its input against
+    /// this pattern separately.
+    ///
+    /// [`LineMatcher`]: ../struct.LineMatcher.html
+    pub fn line_matcher(&self) -> LineMatcher<&'_ A> {
+        LineMatcher::new(self.matcher())
+    }
+
+    /// Returns an iterator over the lines read from the provided
+    /// `io::BufRead` stream that match this pattern.
+    ///
+    /// Each line is matched separately, as described for [`LineMatcher`],
+    /// and the iterator yields the index of each matching line (counting
+    /// from 0) along with its contents, without the line terminator.
+    ///
+    /// If reading from the stream fails, the iterator yields the error, and
+    /// will try to read from the stream again if it is advanced further.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let pattern = Pattern::new_anchored("error: .*").expect("regex is not invalid");
+    /// let log = "info: starting\nerror: oh no\nwarn: hmm\nerror: not again\n";
+    /// let lines = pattern
+    ///     .read_matching_lines(log.as_bytes())
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .expect("reading from a slice does not fail");
+    /// assert_eq!(
+    ///     lines,
+    ///     vec![(1, b"error: oh no".to_vec()), (3, b"error: not again".to_vec())]
+    /// );
+    /// ```
+    ///
+    /// [`LineMatcher`]: ../struct.LineMatcher.html
+    #[cfg(feature = "std")]
+    pub fn read_matching_lines<R: io::BufRead>(&self, reader: R) -> MatchingLines<'_, R, A> {
+        MatchingLines {
+            matcher: self.matcher(),
+            reader,
+            crlf: false,
+            line: 0,
+            buf: Vec::new(),
+        }
+    }
+}
+
+// === impl LineMatcher ===
+
+impl<A: Backend> LineMatcher<A> {
+    /// Returns a new `LineMatcher` that matches each line against the
+    /// pattern of the provided [`Matcher`].
+    ///
+    /// This is useful with owned matchers (see [`Pattern::into_matcher`]);
+    /// otherwise, use [`Pattern::line_matcher`]. The matcher is reset before
+    /// the first line is matched.
+    ///
+    /// [`Matcher`]: ../struct.Matcher.html
+    /// [`Pattern::into_matcher`]: ../struct.Pattern.html#method.into_matcher
+    /// [`Pattern::line_matcher`]: ../struct.Pattern.html#method.line_matcher
+    pub fn new(mut matcher: Matcher<A>) -> Self {
+        matcher.reset();
+        Self {
+            matcher,
+            crlf: false,
+            line: 0,
+            in_line: false,
+            pending_cr: false,
+            matched: Vec::new(),
+        }
+    }
+
+    /// Sets whether a `\r` immediately before a `\n` is treated as part of
+    /// the line terminator, rather than as part of the line.
+    ///
+    /// This is disabled by default. A `\r` that isn't followed by a `\n` is
+    /// always part of the line.
+    pub fn crlf(&mut self, crlf: bool) -> &mut Self {
+        self.crlf = crlf;
+        self
+    }
+
+    /// Returns the indices of the lines that have matched so far, in
+    /// ascending order.
+    ///
+    /// This only includes lines that have been ended by a `\n`; the last line
+    /// of the input is matched by [`finish`].
+    ///
+    /// [`finish`]: #method.finish
+    pub fn matched_lines(&self) -> &[u64] {
+        &self.matched
+    }
+
+    /// Returns the number of lines that have been ended by a `\n` so far.
+    pub fn lines(&self) -> u64 {
+        self.line
+    }
+
+    /// Matches the last line of the input, if it was not ended by a `\n`,
+    /// and returns the indices of every line that matched.
+    pub fn finish(mut self) -> Vec<u64> {
+        if self.in_line {
+            if self.pending_cr {
+                self.matcher.advance_bytes(b"\r");
+            }
+            self.end_line();
+        }
+        self.matched
+    }
+
+    fn push(&mut self, mut bytes: &[u8]) {
+        while let Some(i) = bytes.iter().position(|&b| b == b'\n') {
+            self.push_line(&bytes[..i]);
+            // A `\r` that was held back at the end of the previous write is
+            // part of the terminator, so it is never matched.
+            self.pending_cr = false;
+            self.end_line();
+            bytes = &bytes[i + 1..];
+        }
+        self.push_line(bytes);
+    }
+
+    /// Matches part of a line that contains no `\n`.
+    fn push_line(&mut self, mut bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        self.in_line = true;
+        if self.pending_cr {
+            self.matcher.advance_bytes(b"\r");
+        }
+        // Hold back a trailing `\r` until we know whether a `\n` follows.
+        self.pending_cr = self.crlf && bytes.last() == Some(&b'\r');
+        if self.pending_cr {
+            bytes = &bytes[..bytes.len() - 1];
+        }
+        self.matcher.advance_bytes(bytes);
+    }
+
+    fn end_line(&mut self) {
+        if self.matcher.is_matched() {
+            self.matched.push(self.line);
+        }
+        self.matcher.reset();
+        self.line += 1;
+        self.in_line = false;
+    }
+}
+
+impl<A: Backend> fmt::Write for LineMatcher<A> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Backend> io::Write for LineMatcher<A> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, io::Error> {
+        self.push(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+// === impl MatchingLines ===
+
+#[cfg(feature = "std")]
+impl<R, A: Backend> MatchingLines<'_, R, A> {
+    /// Sets whether a `\r` immediately before a `\n` is treated as part of
+    /// the line terminator, rather than as part of the line.
+    ///
+    /// This is disabled by default.
+    pub fn crlf(&mut self, crlf: bool) -> &mut Self {
+        self.crlf = crlf;
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead, A: Backend> Iterator for MatchingLines<'_, R, A> {
+    type Item = io::Result<(u64, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            }
+            let mut line = &self.buf[..];
+            if let Some(rest) = line.strip_suffix(b"\n") {
+                line = rest;
+                if self.crlf {
+                    line = line.strip_suffix(b"\r").unwrap_or(line);
+                }
+            }
+            let index = self.line;
+            self.line += 1;
+            self.matcher.reset();
+            self.matcher.advance_bytes(line);
+            if self.matcher.is_matched() {
+                return Some(Ok((index, line.to_vec())));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+
+    fn matched_lines(pattern: &Pattern, crlf: bool, chunks: &[&[u8]]) -> Vec<u64> {
+        let mut matcher = pattern.line_matcher();
+        matcher.crlf(crlf);
+        for chunk in chunks {
+            matcher.write_all(chunk).unwrap();
+        }
+        matcher.finish()
+    }
+
+    fn read_lines(pattern: &Pattern, crlf: bool, input: &[u8]) -> Vec<(u64, Vec<u8>)> {
+        let mut lines = pattern.read_matching_lines(input);
+        lines.crlf(crlf);
+        lines.collect::<io::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn anchored_at_each_line() {
+        let pat = Pattern::new_anchored("[0-9]+").unwrap();
+        assert_eq!(
+            matched_lines(&pat, false, &[b"1\na2\n33\n\n4"]),
+            vec![0, 2, 4]
+        );
+    }
+
+    #[test]
+    fn lines_across_writes() {
+        let pat = Pattern::new_anchored("ab+").unwrap();
+        let input = b"abb\nxab\nab\nabbbb";
+        let chunks: Vec<&[u8]> = input.chunks(1).collect();
+        assert_eq!(matched_lines(&pat, false, &chunks), vec![0, 2, 3]);
+        assert_eq!(matched_lines(&pat, false, &[input]), vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn trailing_newline() {
+        let pat = Pattern::new(".*").unwrap();
+        let mut matcher = pat.line_matcher();
+        matcher.write_all(b"a\nb\n").unwrap();
+        assert_eq!(matcher.lines(), 2);
+        assert_eq!(matcher.matched_lines(), &[0, 1]);
+        // There is no empty line after the last `\n`.
+        assert_eq!(matcher.finish(), vec![0, 1]);
+    }
+
+    #[test]
+    fn crlf() {
+        let pat = Pattern::new_anchored("[a-z]+").unwrap();
+        let input: &[u8] = b"ab\r\ncd\r\r\n\rx\r";
+        assert_eq!(matched_lines(&pat, true, &[input]), vec![0]);
+        let chunks: Vec<&[u8]> = input.chunks(1).collect();
+        assert_eq!(matched_lines(&pat, true, &chunks), vec![0]);
+        assert_eq!(matched_lines(&pat, false, &[input]), Vec::<u64>::new());
+
+        let pat = Pattern::new_anchored("[a-z]+\r").unwrap();
+        assert_eq!(matched_lines(&pat, true, &[input]), vec![1]);
+        assert_eq!(matched_lines(&pat, false, &[input]), vec![0]);
+    }
+
+    #[test]
+    fn owned_line_matcher() {
+        let pat = Pattern::new_anchored("b").unwrap();
+        let mut matcher = pat.into_matcher();
+        matcher.write_all(b"xyz").unwrap();
+        let mut matcher = LineMatcher::new(matcher);
+        matcher.write_all(b"b\nc\nb").unwrap();
+        assert_eq!(matcher.finish(), vec![0, 2]);
+    }
+
+    #[test]
+    fn reads_matching_lines() {
+        let pat = Pattern::new_anchored("[0-9]+").unwrap();
+        assert_eq!(
+            read_lines(&pat, false, b"1\na2\n33\n\n4"),
+            vec![(0, b"1".to_vec()), (2, b"33".to_vec()), (4, b"4".to_vec())]
+        );
+        assert_eq!(read_lines(&pat, false, b""), vec![]);
+    }
+
+    #[test]
+    fn reads_crlf_lines() {
+        let pat = Pattern::new_anchored("[a-z]+").unwrap();
+        assert_eq!(
+            read_lines(&pat, true, b"ab\r\ncd\r\r\nef"),
+            vec![(0, b"ab".to_vec()), (2, b"ef".to_vec())]
+        );
+        assert_eq!(
+            read_lines(&pat, false, b"ab\r\nef"),
+            vec![(1, b"ef".to_vec())]
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use crate::search::{Piece, Search};
+use crate::Pattern;
+use std::io;
+
+use regex_automata::dfa::dense::DFA;
+use regex_automata::dfa::Automaton;
+
+/// An `io::Write` adapter that replaces every match of a [`Pattern`] in the
+/// bytes written to it before forwarding them to an inner writer.
+///
+/// Matches are found in the same way as by the [`regex`] crate's
+/// `replace_all`: they are non-overlapping, and where several matches begin
+/// at the same position, the one preferred by the regex's alternations and
+/// repetitions is used. Empty matches are never replaced.
+///
+/// A match may be split across any number of writes, so bytes that could be
+/// the beginning of a match are held back until it is known whether they
+/// match. At most [`max_lookahead`] bytes are held back at a time; if a
+/// possible match grows longer than that, it is cut short, and only the part
+/// matched so far (if any) is replaced.
+///
+/// Bytes that have been held back are written when the writer is
+/// [finished][`finish`] or dropped. Flushing a `RedactingWriter` only flushes
+/// the bytes that are already known not to be part of an unfinished match.
+///
+/// For example:
+/// ```
+/// use matchers::Pattern;
+/// use std::io::Write;
+///
+/// let pattern = Pattern::new(r"token=[0-9a-f]+").expect("regex is not invalid");
+/// let mut writer = pattern.redacting_writer(Vec::new(), "[REDACTED]");
+/// writer.write_all(b"GET /?tok").unwrap();
+/// writer.write_all(b"en=deadbeef HTTP/1.1").unwrap();
+///
+/// let output = writer.finish().unwrap();
+/// assert_eq!(output, b"GET /?[REDACTED] HTTP/1.1");
+/// ```
+///
+/// [`Pattern`]: ../struct.Pattern.html
+/// [`regex`]: https://crates.io/crates/regex
+/// [`max_lookahead`]: #method.max_lookahead
+/// [`finish`]: #method.finish
+#[derive(Debug)]
+pub struct RedactingWriter<W: io::Write, A: Automaton = DFA<Vec<u32>>> {
+    search: Search<A>,
+    replacement: Vec<u8>,
+    /// This is only `None` once the writer has been finished.
+    inner: Option<W>,
+}
+
+// === impl Pattern ===
+
+impl<A: Automaton> Pattern<A> {
+    /// Returns a [`RedactingWriter`] that replaces every match of this
+    /// pattern with `replacement` before writing to `inner`.
+    ///
+    /// [`RedactingWriter`]: ../struct.RedactingWriter.html
+    pub fn redacting_writer<W: io::Write>(
+        &self,
+        inner: W,
+        replacement: impl Into<Vec<u8>>,
+    ) -> RedactingWriter<W, &'_ A> {
+        RedactingWriter {
+            search: Search::new(&self.automaton, self.anchored),
+            replacement: replacement.into(),
+            inner: Some(inner),
+        }
+    }
+}
+
+// === impl RedactingWriter ===
+
+impl<W: io::Write, A: Automaton> RedactingWriter<W, A> {
+    /// Returns a new `RedactingWriter` that owns `pattern`, and replaces
+    /// every match of it with `replacement` before writing to `inner`.
+    ///
+    /// This is useful when the writer must outlive the pattern; otherwise,
+    /// use [`Pattern::redacting_writer`].
+    ///
+    /// [`Pattern::redacting_writer`]: ../struct.Pattern.html#method.redacting_writer
+    pub fn new(pattern: Pattern<A>, inner: W, replacement: impl Into<Vec<u8>>) -> Self {
+        Self {
+            search: Search::new(pattern.automaton, pattern.anchored),
+            replacement: replacement.into(),
+            inner: Some(inner),
+        }
+    }
+
+    /// Sets the maximum number of bytes that will be held back while waiting
+    /// to find out whether they are part of a match.
+    ///
+    /// Matches longer than this are cut short. By default, this is 64 KiB.
+    pub fn max_lookahead(&mut self, limit: usize) -> &mut Self {
+        self.search.set_max_lookahead(limit);
+        self
+    }
+
+    /// Returns the number of bytes that have been written to this
+    /// `RedactingWriter`, but are held back because they may be part of a
+    /// match.
+    pub fn buffered(&self) -> usize {
+        self.search.buffered()
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner
+            .as_ref()
+            .expect("writer is only taken when finished")
+    }
+
+    /// Returns a mutable reference to the inner writer.
+    ///
+    /// Writing to the inner writer directly may interleave its output with
+    /// bytes that are still held back by this `RedactingWriter`.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner
+            .as_mut()
+            .expect("writer is only taken when finished")
+    }
+
+    /// Writes any bytes that were held back, flushes the inner writer, and
+    /// returns it.
+    ///
+    /// This treats the end of the bytes written so far as the end of the
+    /// input, so a match that ends there is replaced.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.search.finish();
+        self.write_resolved()?;
+        let mut inner = self
+            .inner
+            .take()
+            .expect("writer is only taken when finished");
+        inner.flush()?;
+        Ok(inner)
+    }
+
+    /// Writes the bytes that are known to be unmatched, and the replacement
+    /// for every known match, to the inner writer.
+    fn write_resolved(&mut self) -> io::Result<()> {
+        let inner = self
+            .inner
+            .as_mut()
+            .expect("writer is only taken when finished");
+        let replacement = &self.replacement;
+        self.search.drain(|piece| match piece {
+            Piece::Unmatched(bytes) => inner.write_all(bytes),
+            Piece::Match { .. } => inner.write_all(replacement),
+        })
+    }
+}
+
+impl<W: io::Write, A: Automaton> io::Write for RedactingWriter<W, A> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        // Write out anything left over from a previous call that failed
+        // first, so that an error here means that `bytes` were not accepted.
+        self.write_resolved()?;
+        self.search.push(bytes);
+        // The bytes have been accepted, so a failure to write them out now
+        // will be reported by the next call to `write` or `flush` instead.
+        let _ = self.write_resolved();
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_resolved()?;
+        self.get_mut().flush()
+    }
+}
+
+impl<W: io::Write, A: Automaton> Drop for RedactingWriter<W, A> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            self.search.finish();
+            // Errors can't be reported from `drop`; call `finish` to handle
+            // them.
+            let _ = self.write_resolved();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+
+    fn redact(pattern: &Pattern, chunks: &[&str]) -> String {
+        let mut writer = pattern.redacting_writer(Vec::new(), "***");
+        for chunk in chunks {
+            writer.write_all(chunk.as_bytes()).unwrap();
+        }
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn redacts_matches() {
+        let pat = Pattern::new("secret[0-9]+").unwrap();
+        assert_eq!(
+            redact(&pat, &["a secret1 and secret22, no secret"]),
+            "a *** and ***, no secret"
+        );
+    }
+
+    #[test]
+    fn redacts_across_writes() {
+        let pat = Pattern::new("secret[0-9]+").unwrap();
+        let chunks: Vec<String> = "x secret123 y".chars().map(String::from).collect();
+        let chunks: Vec<&str> = chunks.iter().map(String::as_str).collect();
+        assert_eq!(redact(&pat, &chunks), "x *** y");
+    }
+
+    #[test]
+    fn match_at_end_of_input() {
+        let pat = Pattern::new("[0-9]+").unwrap();
+        assert_eq!(redact(&pat, &["pin: 12", "34"]), "pin: ***");
+    }
+
+    #[test]
+    fn no_matches() {
+        let pat = Pattern::new("secret").unwrap();
+        assert_eq!(
+            redact(&pat, &["nothing ", "to see ", "here"]),
+            "nothing to see here"
+        );
+        assert_eq!(redact(&pat, &[]), "");
+    }
+
+    #[test]
+    fn anchored() {
+        let pat = Pattern::new_anchored("[a-z]+:").unwrap();
+        assert_eq!(redact(&pat, &["user: alice: bob:"]), "*** alice: bob:");
+    }
+
+    #[test]
+    fn flush_writes_resolved_bytes() {
+        let pat = Pattern::new("secret[0-9]+").unwrap();
+        let mut writer = pat.redacting_writer(Vec::new(), "***");
+        writer.write_all(b"hello secret4").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.get_ref(), b"hello ");
+        assert_eq!(writer.buffered(), 7);
+        writer.write_all(b"2!").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.get_ref(), b"hello ***!");
+    }
+
+    #[test]
+    fn max_lookahead_bounds_buffering() {
+        let pat = Pattern::new("<[^>]*>").unwrap();
+        let mut writer = pat.redacting_writer(Vec::new(), "***");
+        writer.max_lookahead(8);
+        writer.write_all(b"<a> <unterminated tag").unwrap();
+        writer.flush().unwrap();
+        assert!(writer.get_ref().starts_with(b"*** <unter"));
+        let output = writer.finish().unwrap();
+        assert_eq!(output, b"*** <unterminated tag");
+    }
+
+    #[test]
+    fn drop_writes_held_back_bytes() {
+        let pat = Pattern::new("secret[0-9]+").unwrap();
+        let mut output = Vec::new();
+        {
+            let mut writer = pat.redacting_writer(&mut output, "***");
+            writer.write_all(b"a secret1").unwrap();
+        }
+        assert_eq!(output, b"a ***");
+    }
+
+    #[test]
+    fn owned_writer() {
+        fn assert_static<T: 'static>(_: &T) {}
+
+        let pat = Pattern::new("secret[0-9]+").unwrap();
+        let mut writer = RedactingWriter::new(pat, Vec::new(), "***");
+        assert_static(&writer);
+        writer.write_all(b"secret7").unwrap();
+        assert_eq!(writer.finish().unwrap(), b"***");
+    }
+}
@@ -0,0 +1,369 @@
+//! A streaming search for the non-overlapping, leftmost-first matches of a
+//! pattern, shared by the APIs that need to know *where* matches are, rather
+//! than only whether the input matches.
+//!
+//! The DFAs used by `Pattern`s only report where a match ends, so this runs a
+//! separate anchored search (a "thread") from every offset in the input at
+//! which a match could begin. Once the earliest thread can no longer match,
+//! or has found the longest match it will find, it is resolved: either the
+//! bytes it covers are unmatched, or they are a match, and every other thread
+//! that overlaps it is abandoned. The search then resumes from the end of the
+//! match, re-scanning any input that was buffered past it.
+//!
+//! Two threads in the same state will see the same matches from then on, and
+//! the earlier one takes priority, so a later thread in the same state as an
+//! earlier one is dropped unless it has already found a match of its own.
+//! This bounds the number of live threads by the number of states in the DFA.
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::vec::Vec;
+
+use regex_automata::dfa::Automaton;
+use regex_automata::util::primitives::StateID;
+use regex_automata::util::start;
+use regex_automata::Anchored;
+
+/// The default maximum number of bytes buffered while waiting to find out
+/// whether they are part of a match.
+pub(crate) const DEFAULT_MAX_LOOKAHEAD: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Search<A> {
+    automaton: A,
+    /// If `true`, matches may only begin at the start of the input.
+    anchored: bool,
+    /// The live threads, ordered by the offset at which they started.
+    threads: Vec<Thread>,
+    /// The states of the threads seen so far while deduplicating them.
+    seen: BTreeSet<StateID>,
+    /// Whether every transition out of a state leads to the dead state.
+    ///
+    /// Match states are delayed by one byte, so a thread only enters the
+    /// dead state one byte after its match can no longer be extended. Checking
+    /// for states that can only lead to the dead state lets a match be
+    /// resolved as soon as it ends, rather than when the next byte arrives.
+    dead_ends: BTreeMap<StateID, bool>,
+    /// Input that has not yet been drained, starting at `buf_start`.
+    buf: Vec<u8>,
+    buf_start: u64,
+    /// The byte before `buf_start`, if there is one.
+    look_behind: Option<u8>,
+    /// The offset of the next byte to feed to the threads.
+    scan: u64,
+    /// The offset before which all input is either known to be unmatched, or
+    /// is part of a match in `matches`.
+    resolved: u64,
+    /// Matches that have been resolved but not yet drained.
+    matches: VecDeque<(u64, u64)>,
+    max_lookahead: usize,
+}
+
+/// A piece of resolved input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Piece<'a> {
+    /// Bytes that are not part of any match.
+    Unmatched(&'a [u8]),
+    /// A match spanning the given range of offsets in the input.
+    Match { start: u64, end: u64 },
+}
+
+#[derive(Debug, Clone)]
+struct Thread {
+    start: u64,
+    state: StateID,
+    /// The end of the longest match found by this thread so far.
+    match_end: Option<u64>,
+    /// Whether this thread has finished searching.
+    done: bool,
+}
+
+impl<A: Automaton> Search<A> {
+    pub(crate) fn new(automaton: A, anchored: Anchored) -> Self {
+        Self {
+            automaton,
+            anchored: anchored == Anchored::Yes,
+            threads: Vec::new(),
+            seen: BTreeSet::new(),
+            dead_ends: BTreeMap::new(),
+            buf: Vec::new(),
+            buf_start: 0,
+            look_behind: None,
+            scan: 0,
+            resolved: 0,
+            matches: VecDeque::new(),
+            max_lookahead: DEFAULT_MAX_LOOKAHEAD,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn set_max_lookahead(&mut self, max_lookahead: usize) {
+        self.max_lookahead = max_lookahead;
+    }
+
+    /// Feeds more input to the search, resolving as much of it as possible.
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+        self.run();
+    }
+
+    /// Resolves all of the input provided so far, treating it as the end of
+    /// the input.
+    pub(crate) fn finish(&mut self) {
+        loop {
+            self.run();
+            for thread in self.threads.iter_mut().filter(|t| !t.done) {
+                let eoi = self.automaton.next_eoi_state(thread.state);
+                if self.automaton.is_match_state(eoi) && self.scan > thread.start {
+                    thread.match_end = Some(self.scan);
+                }
+                thread.done = true;
+            }
+            self.threads.retain(|t| t.match_end.is_some());
+            self.resolve();
+            // Resolving a match may leave input after it to be re-scanned.
+            if self.scan == self.buf_end() {
+                debug_assert!(self.threads.is_empty());
+                return;
+            }
+        }
+    }
+
+    /// Returns the number of bytes of input that are buffered because they
+    /// haven't been resolved or drained yet.
+    #[cfg(feature = "std")]
+    pub(crate) fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Passes the input that has been resolved so far to `f`, in order,
+    /// removing it from the buffer.
+    ///
+    /// If `f` returns an error, the piece it was passed is not removed, and
+    /// will be passed to `f` again by the next call to `drain`.
+    pub(crate) fn drain<E>(
+        &mut self,
+        mut f: impl FnMut(Piece<'_>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        while let Some(&(start, end)) = self.matches.front() {
+            if start > self.buf_start {
+                let len = (start - self.buf_start) as usize;
+                f(Piece::Unmatched(&self.buf[..len]))?;
+                self.consume(len);
+            }
+            f(Piece::Match { start, end })?;
+            self.consume((end - self.buf_start) as usize);
+            self.matches.pop_front();
+        }
+        if self.resolved > self.buf_start {
+            let len = (self.resolved - self.buf_start) as usize;
+            f(Piece::Unmatched(&self.buf[..len]))?;
+            self.consume(len);
+        }
+        Ok(())
+    }
+
+    fn consume(&mut self, len: usize) {
+        if len > 0 {
+            self.look_behind = Some(self.buf[len - 1]);
+            self.buf.drain(..len);
+            self.buf_start += len as u64;
+        }
+    }
+
+    fn buf_end(&self) -> u64 {
+        self.buf_start + self.buf.len() as u64
+    }
+
+    fn run(&mut self) {
+        while self.scan < self.buf_end() {
+            self.step();
+            self.resolve();
+        }
+    }
+
+    /// Feeds the byte at `self.scan` to every live thread, first starting a
+    /// new thread at that offset if a match could begin there.
+    fn step(&mut self) {
+        let at = self.scan;
+        let idx = (at - self.buf_start) as usize;
+        if !self.anchored || at == 0 {
+            let look_behind = if idx == 0 {
+                self.look_behind
+            } else {
+                Some(self.buf[idx - 1])
+            };
+            let config = start::Config::new()
+                .anchored(Anchored::Yes)
+                .look_behind(look_behind);
+            let state = self
+                .automaton
+                .start_state(&config)
+                .expect("pattern DFAs support anchored searches and have no quit bytes");
+            self.threads.push(Thread {
+                start: at,
+                state,
+                match_end: None,
+                done: false,
+            });
+        }
+
+        let byte = self.buf[idx];
+        for thread in self.threads.iter_mut().filter(|t| !t.done) {
+            // It's safe to call `next_state_unchecked` since every state was
+            // produced by the same DFA, which can only be constructed by a
+            // `Pattern`.
+            thread.state = unsafe { self.automaton.next_state_unchecked(thread.state, byte) };
+            // Match states are delayed by one byte, so this match ended
+            // before the byte we just consumed. Empty matches are ignored.
+            if self.automaton.is_match_state(thread.state) && at > thread.start {
+                thread.match_end = Some(at);
+            }
+            thread.done = self.automaton.is_dead_state(thread.state);
+            if thread.match_end.is_some() && !thread.done {
+                let automaton = &self.automaton;
+                let state = thread.state;
+                thread.done = *self.dead_ends.entry(state).or_insert_with(|| {
+                    (0..=u8::MAX).all(|b| automaton.is_dead_state(automaton.next_state(state, b)))
+                });
+            }
+        }
+        self.scan += 1;
+
+        let seen = &mut self.seen;
+        seen.clear();
+        self.threads.retain(|thread| {
+            if thread.done {
+                return thread.match_end.is_some();
+            }
+            seen.insert(thread.state) || thread.match_end.is_some()
+        });
+    }
+
+    /// Resolves the earliest threads, if they have finished searching or
+    /// have buffered more than the maximum lookahead.
+    fn resolve(&mut self) {
+        while let Some(front) = self.threads.first() {
+            let forced = self.scan - front.start > self.max_lookahead as u64;
+            if !front.done && !forced {
+                self.resolved = front.start;
+                return;
+            }
+            match front.match_end {
+                Some(end) => {
+                    self.matches.push_back((front.start, end));
+                    self.threads.clear();
+                    self.scan = end;
+                }
+                None => {
+                    self.threads.remove(0);
+                }
+            }
+        }
+        self.resolved = self.scan;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Pattern;
+
+    fn find(pattern: &Pattern, chunks: &[&str]) -> Vec<(u64, u64)> {
+        let mut search = Search::new(&pattern.automaton, pattern.anchored);
+        let mut matches = Vec::new();
+        let mut collect = |piece: Piece<'_>| {
+            if let Piece::Match { start, end } = piece {
+                matches.push((start, end));
+            }
+            Ok::<_, ()>(())
+        };
+        for chunk in chunks {
+            search.push(chunk.as_bytes());
+            search.drain(&mut collect).unwrap();
+        }
+        search.finish();
+        search.drain(&mut collect).unwrap();
+        assert_eq!(search.buffered(), 0);
+        matches
+    }
+
+    #[test]
+    fn leftmost_first() {
+        let pat = Pattern::new("a+b|b").unwrap();
+        assert_eq!(find(&pat, &["xaabxb"]), vec![(1, 4), (5, 6)]);
+
+        let pat = Pattern::new("samwise|sam").unwrap();
+        assert_eq!(find(&pat, &["samwise"]), vec![(0, 7)]);
+        let pat = Pattern::new("sam|samwise").unwrap();
+        assert_eq!(find(&pat, &["samwise"]), vec![(0, 3)]);
+    }
+
+    #[test]
+    fn across_chunks() {
+        let pat = Pattern::new("[0-9]+").unwrap();
+        assert_eq!(
+            find(&pat, &["a1", "23b4", "", "5", "6"]),
+            vec![(1, 4), (5, 8)]
+        );
+    }
+
+    #[test]
+    fn rescans_after_match() {
+        // The search for `abcd` reads past the end of the match for `ab`, so
+        // the match for `cx` is only found by re-scanning.
+        let pat = Pattern::new("abcd|ab|cx").unwrap();
+        assert_eq!(find(&pat, &["abcx"]), vec![(0, 2), (2, 4)]);
+    }
+
+    #[test]
+    fn match_after_match() {
+        let pat = Pattern::new("ab|b+c").unwrap();
+        assert_eq!(find(&pat, &["abbc"]), vec![(0, 2), (2, 4)]);
+        assert_eq!(find(&pat, &["a", "b", "b", "c"]), vec![(0, 2), (2, 4)]);
+    }
+
+    #[test]
+    fn empty_matches_are_ignored() {
+        let pat = Pattern::new("a*").unwrap();
+        assert_eq!(find(&pat, &["baab"]), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn anchored() {
+        let pat = Pattern::new_anchored("a+").unwrap();
+        assert_eq!(find(&pat, &["aaba"]), vec![(0, 2)]);
+        assert_eq!(find(&pat, &["baa"]), vec![]);
+    }
+
+    #[test]
+    fn look_behind() {
+        let pat = Pattern::builder().unicode(false).build(r"\bfoo\b").unwrap();
+        assert_eq!(find(&pat, &["foo xfoo f", "oo"]), vec![(0, 3), (9, 12)]);
+
+        let pat = Pattern::builder().multi_line(true).build("^x").unwrap();
+        assert_eq!(find(&pat, &["xx\n", "x"]), vec![(0, 1), (3, 4)]);
+    }
+
+    #[test]
+    fn max_lookahead() {
+        let pat = Pattern::new("a+b").unwrap();
+        let mut search = Search::new(&pat.automaton, pat.anchored);
+        search.set_max_lookahead(4);
+        let mut matches = Vec::new();
+        let mut collect = |piece: Piece<'_>| {
+            if let Piece::Match { start, end } = piece {
+                matches.push((start, end));
+            }
+            Ok::<_, ()>(())
+        };
+        search.push(b"aaaaaaaaaa");
+        search.drain(&mut collect).unwrap();
+        assert!(search.buffered() <= 5);
+        search.push(b"ab");
+        search.finish();
+        search.drain(&mut collect).unwrap();
+        // The match is too long to be found in full.
+        assert_eq!(matches.len(), 1);
+        assert_ne!(matches[0], (0, 12));
+        assert_eq!(matches[0].1, 12);
+    }
+}
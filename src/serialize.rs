@@ -0,0 +1,251 @@
+use crate::Pattern;
+use alloc::{vec, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use regex_automata::dfa::dense::DFA;
+use regex_automata::util::wire;
+use regex_automata::Anchored;
+
+/// The size of the trailer that records how a serialized pattern is anchored.
+const TRAILER_LEN: usize = 4;
+
+/// The alignment required to deserialize a dense DFA.
+const ALIGN: usize = core::mem::align_of::<u32>();
+
+/// An error that occurred while deserializing a [`Pattern`] from bytes.
+///
+/// [`Pattern`]: ../struct.Pattern.html
+#[derive(Debug)]
+pub struct DeserializeError {
+    kind: ErrorKind,
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    Dfa(wire::DeserializeError),
+    BufferTooSmall,
+    InvalidAnchored(u32),
+    UnsupportedAnchored,
+}
+
+// === impl Pattern ===
+
+impl<T: AsRef<[u32]>> Pattern<DFA<T>> {
+    fn to_bytes_with(
+        &self,
+        trailer: [u8; TRAILER_LEN],
+        write: impl FnOnce(&DFA<T>, &mut [u8]) -> Result<usize, wire::SerializeError>,
+    ) -> (Vec<u8>, usize) {
+        let len = self.automaton.write_to_len();
+        // Allocate room for any padding up front, since growing the buffer
+        // afterwards could move it to a differently aligned address.
+        let mut bytes = vec![0u8; ALIGN - 1 + len + TRAILER_LEN];
+        let padding = bytes.as_ptr().align_offset(ALIGN);
+        let nwrite = write(&self.automaton, &mut bytes[padding..])
+            .expect("buffer is large enough to serialize the DFA");
+        let end = padding + nwrite;
+        bytes[end..end + TRAILER_LEN].copy_from_slice(&trailer);
+        bytes.truncate(end + TRAILER_LEN);
+        (bytes, padding)
+    }
+
+    /// Serializes this pattern to raw bytes in little endian format.
+    ///
+    /// This returns the bytes along with the number of padding bytes at the
+    /// beginning of the buffer, which are needed to ensure that the DFA is
+    /// correctly aligned in memory. The serialized pattern (without padding)
+    /// may be written to a file, and later loaded with
+    /// [`Pattern::from_bytes`] on a little endian target.
+    ///
+    /// Both the DFA and how the pattern is anchored are stored in the
+    /// returned bytes.
+    ///
+    /// [`Pattern::from_bytes`]: #method.from_bytes
+    pub fn to_bytes_little_endian(&self) -> (Vec<u8>, usize) {
+        let trailer = encode_anchored(self.anchored).to_le_bytes();
+        self.to_bytes_with(trailer, |dfa, buf| dfa.write_to_little_endian(buf))
+    }
+
+    /// Serializes this pattern to raw bytes in big endian format.
+    ///
+    /// See [`Pattern::to_bytes_little_endian`] for details.
+    ///
+    /// [`Pattern::to_bytes_little_endian`]: #method.to_bytes_little_endian
+    pub fn to_bytes_big_endian(&self) -> (Vec<u8>, usize) {
+        let trailer = encode_anchored(self.anchored).to_be_bytes();
+        self.to_bytes_with(trailer, |dfa, buf| dfa.write_to_big_endian(buf))
+    }
+
+    /// Serializes this pattern to raw bytes in the native endianness of the
+    /// current target.
+    ///
+    /// See [`Pattern::to_bytes_little_endian`] for details.
+    ///
+    /// For example:
+    /// ```
+    /// use matchers::Pattern;
+    ///
+    /// let pattern = Pattern::new_anchored("a+b").expect("regex is not invalid");
+    /// let (bytes, padding) = pattern.to_bytes_native_endian();
+    ///
+    /// let (loaded, _) = Pattern::from_bytes(&bytes[padding..])
+    ///     .expect("serialized pattern is valid");
+    /// assert!(loaded.display_matches(&"aaaab"));
+    /// assert!(!loaded.display_matches(&"hello world! aaaab"));
+    /// ```
+    ///
+    /// [`Pattern::to_bytes_little_endian`]: #method.to_bytes_little_endian
+    pub fn to_bytes_native_endian(&self) -> (Vec<u8>, usize) {
+        let trailer = encode_anchored(self.anchored).to_ne_bytes();
+        self.to_bytes_with(trailer, |dfa, buf| dfa.write_to_native_endian(buf))
+    }
+}
+
+impl<'a> Pattern<DFA<&'a [u32]>> {
+    /// Deserializes a pattern from bytes in the native endianness of the
+    /// current target, without copying the DFA.
+    ///
+    /// The bytes must have been produced by [`Pattern::to_bytes_native_endian`]
+    /// (or the little/big endian variant matching the current target), and
+    /// must be aligned to a 4 byte boundary. The DFA is validated before it
+    /// is returned, so it is safe to call this with untrusted input.
+    ///
+    /// On success, this returns the pattern along with the number of bytes
+    /// that were read from `slice`.
+    ///
+    /// [`Pattern::to_bytes_native_endian`]: #method.to_bytes_native_endian
+    pub fn from_bytes(slice: &'a [u8]) -> Result<(Self, usize), DeserializeError> {
+        let (automaton, nread) = DFA::from_bytes(slice).map_err(ErrorKind::Dfa)?;
+        let trailer = slice
+            .get(nread..nread + TRAILER_LEN)
+            .ok_or(ErrorKind::BufferTooSmall)?;
+        let mut raw = [0u8; TRAILER_LEN];
+        raw.copy_from_slice(trailer);
+        let anchored = decode_anchored(u32::from_ne_bytes(raw))?;
+
+        // Matchers assume that the DFA has a start state for the pattern's
+        // anchoring mode, and searches for the positions of matches always
+        // start anchored, so check that here rather than panicking later.
+        for mode in [anchored, Anchored::Yes] {
+            let config = regex_automata::util::start::Config::new().anchored(mode);
+            regex_automata::dfa::Automaton::start_state(&automaton, &config)
+                .map_err(|_| ErrorKind::UnsupportedAnchored)?;
+        }
+
+        let pattern = Pattern {
+            automaton,
+            anchored,
+            captures: None,
+        };
+        Ok((pattern, nread + TRAILER_LEN))
+    }
+}
+
+fn encode_anchored(anchored: Anchored) -> u32 {
+    match anchored {
+        Anchored::No => 0,
+        Anchored::Yes => 1,
+        Anchored::Pattern(_) => unreachable!("patterns are never anchored to a pattern ID"),
+    }
+}
+
+fn decode_anchored(raw: u32) -> Result<Anchored, ErrorKind> {
+    match raw {
+        0 => Ok(Anchored::No),
+        1 => Ok(Anchored::Yes),
+        _ => Err(ErrorKind::InvalidAnchored(raw)),
+    }
+}
+
+// === impl DeserializeError ===
+
+impl From<ErrorKind> for DeserializeError {
+    fn from(kind: ErrorKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Dfa(ref e) => write!(f, "invalid serialized DFA: {}", e),
+            ErrorKind::BufferTooSmall => {
+                f.write_str("buffer is too small to contain a serialized pattern")
+            }
+            ErrorKind::InvalidAnchored(raw) => {
+                write!(f, "invalid anchoring mode in serialized pattern: {}", raw)
+            }
+            ErrorKind::UnsupportedAnchored => {
+                f.write_str("serialized DFA does not support the pattern's anchoring mode")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DeserializeError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip_unanchored() {
+        let pattern = Pattern::new("a+b").unwrap();
+        let (bytes, padding) = pattern.to_bytes_native_endian();
+        let (loaded, nread) = Pattern::from_bytes(&bytes[padding..]).unwrap();
+        assert_eq!(nread, bytes.len() - padding);
+        assert!(loaded.display_matches(&"hello world! aaab"));
+        assert!(!loaded.display_matches(&"aaabb"));
+    }
+
+    #[test]
+    fn roundtrip_anchored() {
+        let pattern = Pattern::new_anchored("a+b").unwrap();
+        let (bytes, padding) = pattern.to_bytes_native_endian();
+        let (loaded, _) = Pattern::from_bytes(&bytes[padding..]).unwrap();
+        assert!(loaded.display_matches(&"aaab"));
+        assert!(!loaded.display_matches(&"hello world! aaab"));
+    }
+
+    #[test]
+    fn roundtrip_explicit_endianness() {
+        let pattern = Pattern::new("hello").unwrap();
+        let (bytes, padding) = if cfg!(target_endian = "little") {
+            pattern.to_bytes_little_endian()
+        } else {
+            pattern.to_bytes_big_endian()
+        };
+        let (loaded, _) = Pattern::from_bytes(&bytes[padding..]).unwrap();
+        assert!(loaded.display_matches(&"why, hello"));
+    }
+
+    #[test]
+    fn missing_trailer() {
+        let pattern = Pattern::new("hello").unwrap();
+        let (bytes, padding) = pattern.to_bytes_native_endian();
+        let truncated = &bytes[padding..bytes.len() - TRAILER_LEN];
+        assert!(matches!(
+            Pattern::from_bytes(truncated),
+            Err(DeserializeError {
+                kind: ErrorKind::BufferTooSmall
+            })
+        ));
+    }
+
+    #[test]
+    fn invalid_trailer() {
+        let pattern = Pattern::new("hello").unwrap();
+        let (mut bytes, padding) = pattern.to_bytes_native_endian();
+        let len = bytes.len();
+        bytes[len - TRAILER_LEN..].copy_from_slice(&7u32.to_ne_bytes());
+        assert!(matches!(
+            Pattern::from_bytes(&bytes[padding..]),
+            Err(DeserializeError {
+                kind: ErrorKind::InvalidAnchored(7)
+            })
+        ));
+    }
+}
@@ -0,0 +1,346 @@
+#[cfg(feature = "std")]
+use crate::READ_BUF_LEN;
+use crate::{Error, PatternBuilder};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use regex_automata::dfa::dense::DFA;
+use regex_automata::dfa::Automaton;
+use regex_automata::util::primitives::{PatternID, StateID};
+use regex_automata::Anchored;
+
+/// A compiled set of match patterns that can test which of several regexes
+/// match an input, while only formatting or reading that input once.
+///
+/// A `PatternSet` is compiled into a single automaton, so matching an input
+/// against a set of many patterns is significantly cheaper than matching it
+/// against each pattern in turn.
+///
+/// A pattern in the set matches an input if a match of that pattern ends at
+/// the end of the input. For unanchored sets, that match may begin anywhere
+/// in the input; for anchored sets, it must begin at the first byte.
+///
+/// For example:
+/// ```
+/// use matchers::PatternSet;
+///
+/// let set = PatternSet::new(&["hello", "world", r"hel+o w[orl]{3}d", "goodbye"])
+///     .expect("regexes are not invalid");
+///
+/// let matches = set.display_matches(&"hello world");
+/// assert_eq!(matches.len(), 2);
+/// assert_eq!(matches.iter().map(|id| id.as_usize()).collect::<Vec<_>>(), vec![1, 2]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PatternSet<A = DFA<Vec<u32>>> {
+    pub(crate) automaton: A,
+    pub(crate) anchored: Anchored,
+}
+
+/// A reference to a [`PatternSet`] that matches a single input.
+///
+/// [`PatternSet`]: ../struct.PatternSet.html
+#[derive(Debug, Clone)]
+pub struct SetMatcher<A = DFA<Vec<u32>>> {
+    automaton: A,
+    state: StateID,
+}
+
+/// The set of patterns in a [`PatternSet`] that matched an input.
+///
+/// [`PatternSet`]: ../struct.PatternSet.html
+#[derive(Debug, Clone)]
+pub struct SetMatches {
+    matched: regex_automata::PatternSet,
+}
+
+// === impl PatternSet ===
+
+impl PatternSet {
+    /// Returns a new `PatternSet` for the given regexes, or an error if any
+    /// of the regexes were invalid.
+    ///
+    /// Each pattern is identified by its index in the provided iterator. Like
+    /// [`Pattern::new`], the returned set is unanchored, so the patterns may
+    /// be preceded by any number of non-matching characters.
+    ///
+    /// [`Pattern::new`]: ../struct.Pattern.html#method.new
+    pub fn new<I, P>(patterns: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<str>,
+    {
+        PatternBuilder::new().build_set(patterns)
+    }
+
+    /// Returns a new `PatternSet` for the given regexes, anchored at the
+    /// beginning of the input stream, or an error if any of the regexes were
+    /// invalid.
+    ///
+    /// See [`Pattern::new_anchored`] for details on anchoring.
+    ///
+    /// [`Pattern::new_anchored`]: ../struct.Pattern.html#method.new_anchored
+    pub fn new_anchored<I, P>(patterns: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<str>,
+    {
+        PatternBuilder::new().anchored(true).build_set(patterns)
+    }
+}
+
+impl<A: Automaton> PatternSet<A> {
+    /// Obtains a [`SetMatcher`] for this pattern set.
+    ///
+    /// This is useful when wanting to incrementally feed input (via
+    /// `io::Write`/`fmt::Write`) to a matcher. Otherwise, the convenience
+    /// methods on `PatternSet` suffice.
+    ///
+    /// [`SetMatcher`]: ../struct.SetMatcher.html
+    pub fn matcher(&self) -> SetMatcher<&'_ A> {
+        let config = regex_automata::util::start::Config::new().anchored(self.anchored);
+        SetMatcher {
+            automaton: &self.automaton,
+            state: self.automaton.start_state(&config).unwrap(),
+        }
+    }
+
+    /// Returns the number of patterns in this set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.automaton.pattern_len()
+    }
+
+    /// Returns `true` if this set contains no patterns.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the patterns in this set that match the given string.
+    #[inline]
+    pub fn matches(&self, s: &impl AsRef<str>) -> SetMatches {
+        self.matcher().matches(s)
+    }
+
+    /// Returns the patterns in this set that match the formatted output of
+    /// the given type implementing `fmt::Debug`.
+    ///
+    /// The value is only formatted once, regardless of the number of
+    /// patterns in the set.
+    #[inline]
+    pub fn debug_matches(&self, d: &impl fmt::Debug) -> SetMatches {
+        self.matcher().debug_matches(d)
+    }
+
+    /// Returns the patterns in this set that match the formatted output of
+    /// the given type implementing `fmt::Display`.
+    ///
+    /// The value is only formatted once, regardless of the number of
+    /// patterns in the set.
+    #[inline]
+    pub fn display_matches(&self, d: &impl fmt::Display) -> SetMatches {
+        self.matcher().display_matches(d)
+    }
+
+    /// Returns either the patterns in this set that match the data read from
+    /// the provided `io::Read` stream, or an `io::Error` if an error occurred
+    /// reading from the stream.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn read_matches(&self, io: impl io::Read) -> io::Result<SetMatches> {
+        self.matcher().read_matches(io)
+    }
+}
+
+// === impl SetMatcher ===
+
+impl<A: Automaton> SetMatcher<A> {
+    #[inline]
+    fn advance(&mut self, input: u8) {
+        // It's safe to call `next_state_unchecked` since the matcher may
+        // only be constructed by a `PatternSet`, which, in turn, can only be
+        // constructed with a valid DFA.
+        self.state = unsafe { self.automaton.next_state_unchecked(self.state, input) };
+    }
+
+    /// Advances the automaton over a chunk of input, stopping early if it
+    /// enters a dead state. Returns the number of bytes consumed.
+    #[inline]
+    fn advance_bytes(&mut self, bytes: &[u8]) -> usize {
+        for (i, &byte) in bytes.iter().enumerate() {
+            self.advance(byte);
+            if self.automaton.is_dead_state(self.state) {
+                return i + 1;
+            }
+        }
+        bytes.len()
+    }
+
+    /// Returns the patterns that have matched the input provided to this
+    /// `SetMatcher` so far.
+    pub fn matched(&self) -> SetMatches {
+        let mut matched = regex_automata::PatternSet::new(self.automaton.pattern_len());
+        let eoi_state = self.automaton.next_eoi_state(self.state);
+        if self.automaton.is_match_state(eoi_state) {
+            for i in 0..self.automaton.match_len(eoi_state) {
+                matched.insert(self.automaton.match_pattern(eoi_state, i));
+            }
+        }
+        SetMatches { matched }
+    }
+
+    /// Returns the patterns that match the given string.
+    pub fn matches(mut self, s: &impl AsRef<str>) -> SetMatches {
+        self.advance_bytes(s.as_ref().as_bytes());
+        self.matched()
+    }
+
+    /// Returns the patterns that match the formatted output of the given type
+    /// implementing `fmt::Debug`.
+    pub fn debug_matches(mut self, d: &impl fmt::Debug) -> SetMatches {
+        use core::fmt::Write;
+        write!(&mut self, "{:?}", d).expect("matcher write impl should not fail");
+        self.matched()
+    }
+
+    /// Returns the patterns that match the formatted output of the given type
+    /// implementing `fmt::Display`.
+    pub fn display_matches(mut self, d: &impl fmt::Display) -> SetMatches {
+        use core::fmt::Write;
+        write!(&mut self, "{}", d).expect("matcher write impl should not fail");
+        self.matched()
+    }
+
+    /// Returns either the patterns that match the data read from the
+    /// provided `io::Read` stream, or an `io::Error` if an error occurred
+    /// reading from the stream.
+    #[cfg(feature = "std")]
+    pub fn read_matches(mut self, mut io: impl io::Read + Sized) -> io::Result<SetMatches> {
+        let mut buf = [0u8; READ_BUF_LEN];
+        loop {
+            let n = match io.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.advance_bytes(&buf[..n]);
+            if self.automaton.is_dead_state(self.state) {
+                break;
+            }
+        }
+        Ok(self.matched())
+    }
+}
+
+impl<A: Automaton> fmt::Write for SetMatcher<A> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.advance_bytes(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Automaton> io::Write for SetMatcher<A> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, io::Error> {
+        Ok(self.advance_bytes(bytes))
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+// === impl SetMatches ===
+
+impl SetMatches {
+    /// Returns `true` if the pattern with the given ID matched.
+    #[inline]
+    pub fn matched(&self, id: PatternID) -> bool {
+        self.matched.contains(id)
+    }
+
+    /// Returns `true` if any pattern in the set matched.
+    #[inline]
+    pub fn matched_any(&self) -> bool {
+        !self.matched.is_empty()
+    }
+
+    /// Returns the number of patterns that matched.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.matched.len()
+    }
+
+    /// Returns `true` if no patterns matched.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.matched.is_empty()
+    }
+
+    /// Returns an iterator over the IDs of the patterns that matched, in
+    /// ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = PatternID> + '_ {
+        self.matched.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ids(matches: SetMatches) -> Vec<usize> {
+        matches.iter().map(|id| id.as_usize()).collect()
+    }
+
+    #[test]
+    fn debug_matches() {
+        let set = PatternSet::new([r"Some\([0-9]+\)", "None", r"[0-9]+\)", "goodbye"]).unwrap();
+        assert_eq!(set.len(), 4);
+        assert_eq!(ids(set.debug_matches(&Some(42))), vec![0, 2]);
+        assert_eq!(ids(set.debug_matches(&None::<usize>)), vec![1]);
+        assert!(set.debug_matches(&Some("nothing")).is_empty());
+    }
+
+    #[test]
+    fn display_matches() {
+        let set = PatternSet::new(["a+b", "b", "c"]).unwrap();
+        let matches = set.display_matches(&"xxaab");
+        assert!(matches.matched(PatternID::must(0)));
+        assert!(matches.matched(PatternID::must(1)));
+        assert!(!matches.matched(PatternID::must(2)));
+        assert!(matches.matched_any());
+    }
+
+    #[test]
+    fn reader_matches() {
+        let set = PatternSet::new(["hello world", "goodbye world"]).unwrap();
+        let matches = set.read_matches(&b"hello world"[..]).unwrap();
+        assert_eq!(ids(matches), vec![0]);
+    }
+
+    #[test]
+    fn anchored() {
+        let set = PatternSet::new_anchored(["a+b", "f+a+b"]).unwrap();
+        assert_eq!(ids(set.display_matches(&"aab")), vec![0]);
+        assert_eq!(ids(set.display_matches(&"ffaab")), vec![1]);
+        assert!(set.display_matches(&"qqaab").is_empty());
+    }
+
+    #[test]
+    fn incremental() {
+        use std::io::Write;
+
+        let set = PatternSet::new(["foo", "bar"]).unwrap();
+        let mut matcher = set.matcher();
+        matcher.write_all(b"fo").unwrap();
+        assert!(matcher.matched().is_empty());
+        matcher.write_all(b"o").unwrap();
+        assert_eq!(ids(matcher.matched()), vec![0]);
+        matcher.write_all(b" bar").unwrap();
+        assert_eq!(ids(matcher.matched()), vec![1]);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{Backend, Matcher, Pattern, READ_BUF_LEN};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+
+// === impl Pattern ===
+
+impl<A: Backend> Pattern<A> {
+    /// Returns either a `bool` indicating whether or not this pattern matches the
+    /// data read from the provided `AsyncRead` stream, or an `io::Error` if an
+    /// error occurred reading from the stream.
+    ///
+    /// This requires the `tokio` feature.
+    ///
+    /// For example:
+    /// ```
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// use matchers::Pattern;
+    ///
+    /// let pattern = Pattern::new("hello world").expect("regex is not invalid");
+    /// let matched = pattern
+    ///     .async_read_matches(&b"hello world"[..])
+    ///     .await
+    ///     .expect("reading from a slice does not fail");
+    /// assert!(matched);
+    /// # }
+    /// ```
+    #[inline]
+    pub async fn async_read_matches(&self, io: impl AsyncRead) -> io::Result<bool> {
+        self.matcher().async_read_matches(io).await
+    }
+}
+
+// === impl Matcher ===
+
+impl<A: Backend> Matcher<A> {
+    /// Returns either a `bool` indicating whether or not this pattern matches the
+    /// data read from the provided `AsyncRead` stream, or an `io::Error` if an
+    /// error occurred reading from the stream.
+    ///
+    /// Like [`read_matches`], the stream is read in chunks into a fixed-size
+    /// buffer, and reading stops as soon as the input can no longer match.
+    ///
+    /// This requires the `tokio` feature.
+    ///
+    /// [`read_matches`]: #method.read_matches
+    pub async fn async_read_matches(mut self, io: impl AsyncRead) -> io::Result<bool> {
+        let mut io = std::pin::pin!(io);
+        let mut buf = [0u8; READ_BUF_LEN];
+        loop {
+            let n = match io.read(&mut buf).await {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.advance_bytes(&buf[..n]);
+            if self.automaton.is_dead_state(self.state) {
+                return Ok(false);
+            }
+        }
+        Ok(self.is_matched())
+    }
+}
+
+/// Matching never blocks, so writes to a `Matcher` always complete
+/// immediately, consuming input in the same way as its `io::Write`
+/// implementation.
+///
+/// This requires the `tokio` feature.
+impl<A: Backend> AsyncWrite for Matcher<A> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        bytes: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(self.get_mut().advance_bytes(bytes)))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn duplex_read_matches() {
+        let pattern = Pattern::new("hello world").unwrap();
+        let (mut tx, rx) = tokio::io::duplex(4);
+        let write = async move {
+            tx.write_all(b"why, hello world").await.unwrap();
+            // Dropping the writer closes the stream.
+        };
+        let (matched, ()) = tokio::join!(pattern.async_read_matches(rx), write);
+        assert!(matched.unwrap());
+    }
+
+    #[tokio::test]
+    async fn duplex_read_does_not_match() {
+        let pattern = Pattern::new("hello world").unwrap();
+        let (mut tx, rx) = tokio::io::duplex(64);
+        tx.write_all(b"goodbye world").await.unwrap();
+        drop(tx);
+        assert!(!pattern.async_read_matches(rx).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_stops_at_dead_state() {
+        let pattern = Pattern::new_anchored("a+b").unwrap();
+        // The writer never closes the stream, so this would wait forever if
+        // the matcher kept reading after the pattern could no longer match.
+        let (mut tx, rx) = tokio::io::duplex(64);
+        tx.write_all(b"qqq").await.unwrap();
+        assert!(!pattern.async_read_matches(rx).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn async_write() {
+        let pattern = Pattern::new("a+b").unwrap();
+        let mut matcher = pattern.matcher();
+        matcher.write_all(b"ffaa").await.unwrap();
+        assert!(!matcher.is_matched());
+        matcher.write_all(b"ab").await.unwrap();
+        matcher.shutdown().await.unwrap();
+        assert!(matcher.is_matched());
+    }
+
+    #[tokio::test]
+    async fn copy_from_duplex() {
+        let pattern = Pattern::new("[0-9]+ bytes").unwrap();
+        let mut matcher = pattern.matcher();
+        let (mut tx, mut rx) = tokio::io::duplex(8);
+        let write = async move {
+            tx.write_all(b"sent 1024 bytes").await.unwrap();
+        };
+        let (copied, ()) = tokio::join!(tokio::io::copy(&mut rx, &mut matcher), write);
+        assert_eq!(copied.unwrap(), 15);
+        assert!(matcher.is_matched());
+    }
+}